@@ -1,25 +1,79 @@
-use clap::Parser;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+
+use super::client::Order;
+use super::config::HttpVersion;
+use super::history::StatusFilter;
+use super::Output;
 
 #[derive(Parser)]
 #[command(name = "ycurl")]
 #[command(version)]
 pub struct Args {
-    /// Config file
-    #[arg(short, long, default_value = "./ycurl.json")]
-    pub file: String,
+    #[command(subcommand)]
+    pub command: Option<Command>,
 
-    /// Index or name of the request sent
+    /// Config file [default: `ycurl.json` in the current directory or the nearest parent directory]
+    #[arg(short, long, global = true)]
+    pub file: Option<String>,
+
+    /// Index or name of the request sent (two or more are sent concurrently)
     #[arg()]
-    pub index: Option<String>,
+    pub index: Vec<String>,
+
+    /// Send all of the requests in the group concurrently
+    #[arg(short, long, value_name = "NAME", conflicts_with = "index")]
+    pub group: Option<String>,
+
+    /// Maximum number of requests sent at a time
+    #[arg(short, long, value_name = "N", default_value_t = 8, value_parser = clap::value_parser!(u16).range(1..))]
+    pub jobs: u16,
+
+    /// Order in which the results of the requests sent concurrently are printed
+    #[arg(long, value_enum, default_value_t = Order::Definition)]
+    pub order: Order,
+
+    /// Output format of the responses
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = Output::Pretty)]
+    pub output: Output,
+
+    /// Write the response body to a file instead of printing it
+    #[arg(short, long, value_name = "FILE", conflicts_with = "group")]
+    pub output_file: Option<String>,
+
+    /// Apply a jq expression (e.g. `.items[].id`) or JSONPath (e.g. `$.items[*].id`) to the JSON response body
+    #[arg(long, value_name = "EXPR")]
+    pub filter: Option<String>,
 
     /// Show response headers
     #[arg(long)]
     pub show_headers: bool,
 
+    /// Show the timing breakdown, the HTTP version and the size of the response
+    #[arg(long)]
+    pub timing: bool,
+
+    /// HTTP version used: `auto` (negotiated via ALPN), `1.1`, or `2` (prior knowledge)
+    #[arg(long, value_enum, value_name = "VERSION", hide_possible_values = true)]
+    pub http: Option<HttpVersion>,
+
+    /// Request a compressed response (gzip, deflate, br or zstd) and decode it
+    #[arg(long)]
+    pub compressed: bool,
+
     /// Disable following redirects
     #[arg(long)]
     pub disable_redirect: bool,
 
+    /// Maximum number of redirects followed [default: 10]
+    #[arg(long, value_name = "N")]
+    pub max_redirects: Option<usize>,
+
+    /// Follow only the redirects to the host (and port) of the request
+    #[arg(long)]
+    pub same_host_redirects: bool,
+
     /// Allow `disabled` request to be sent
     #[arg(long)]
     pub include_disabled: bool,
@@ -28,6 +82,14 @@ pub struct Args {
     #[arg(long)]
     pub show_config: bool,
 
+    /// Append the request and the response to a HAR file
+    #[arg(long, value_name = "FILE")]
+    pub export_har: Option<String>,
+
+    /// Print the entries of a HAR file as requests and exit
+    #[arg(long, value_name = "FILE")]
+    pub import_har: Option<String>,
+
     /// Output shell completion code
     #[arg(long)]
     pub complete: bool,
@@ -36,3 +98,53 @@ pub struct Args {
     #[arg(short, long)]
     pub verbose: bool,
 }
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Check the config file and report all of the problems found
+    #[command(visible_alias = "validate")]
+    Lint,
+    /// Print the JSON Schema of the config file
+    Schema,
+    /// Send a request repeatedly and report the throughput and the latency
+    Bench {
+        /// Index or name of the request sent
+        index: String,
+
+        /// Number of requests sent [default: 200 unless `--duration` is specified]
+        #[arg(short = 'n', long, value_name = "N")]
+        requests: Option<u64>,
+
+        /// Number of requests sent at a time
+        #[arg(short, long, value_name = "N", default_value_t = 10, value_parser = clap::value_parser!(u16).range(1..))]
+        concurrency: u16,
+
+        /// Keep sending requests for the duration (e.g. `30s`, `1m`)
+        #[arg(short, long, value_parser = humantime::parse_duration)]
+        duration: Option<Duration>,
+    },
+    /// List the operations of the GraphQL API of a request by schema introspection
+    Introspect {
+        /// Index or name of the request whose URL and headers are used
+        index: String,
+    },
+    /// List the requests sent in the past, which are recorded in `~/logs/ycurl.jsonl`
+    History {
+        /// Show only the requests with the name
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Show only the requests with the status (e.g. `404`, `5xx`), or `error` for the failed ones
+        #[arg(long)]
+        status: Option<StatusFilter>,
+
+        /// Number of the most recent requests shown
+        #[arg(short = 'n', long, value_name = "N", default_value_t = 20)]
+        limit: usize,
+    },
+    /// Resend a past request exactly as recorded, regardless of the current config
+    Replay {
+        /// ID of the request shown by `history`
+        id: usize,
+    },
+}
@@ -0,0 +1,288 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use hdrhistogram::Histogram;
+
+use super::client::Client;
+use super::error::Error;
+use super::format_duration;
+
+//the number of requests sent when neither `requests` nor `duration` is specified
+const DEFAULT_REQUESTS: u64 = 200;
+
+const HISTOGRAM_BUCKETS: u64 = 10;
+const HISTOGRAM_WIDTH: u64 = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    pub requests: Option<u64>,
+    pub concurrency: usize,
+    pub duration: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub concurrency: usize,
+    pub elapsed: Duration,
+    //the number of responses by status code
+    pub statuses: BTreeMap<u16, u64>,
+    //the number of requests which failed without a response, by error message
+    pub errors: BTreeMap<String, u64>,
+    //latencies of the responses in microseconds
+    pub latencies: Histogram<u64>,
+}
+
+impl Report {
+    fn new(concurrency: usize) -> Self {
+        Self {
+            concurrency,
+            elapsed: Duration::ZERO,
+            statuses: BTreeMap::new(),
+            errors: BTreeMap::new(),
+            //from 1µs to 1h with 3 significant digits
+            latencies: Histogram::new_with_bounds(1, 3_600_000_000, 3).unwrap(),
+        }
+    }
+
+    fn merge(&mut self, other: Report) {
+        for (k, v) in other.statuses {
+            *self.statuses.entry(k).or_default() += v;
+        }
+        for (k, v) in other.errors {
+            *self.errors.entry(k).or_default() += v;
+        }
+        self.latencies.add(other.latencies).unwrap();
+    }
+
+    pub fn total(&self) -> u64 {
+        self.statuses.values().sum::<u64>() + self.errors.values().sum::<u64>()
+    }
+
+    //requests per second
+    pub fn throughput(&self) -> f64 {
+        self.total() as f64 / self.elapsed.as_secs_f64()
+    }
+
+    //`q` is in `0.0..=100.0`
+    pub fn percentile(&self, q: f64) -> Duration {
+        Duration::from_micros(self.latencies.value_at_percentile(q))
+    }
+
+    pub fn print(&self) {
+        println!(
+            "Requests:     {} ({} concurrent)",
+            self.total(),
+            self.concurrency
+        );
+        println!("Duration:     {}", format_duration(self.elapsed));
+        println!("Throughput:   {:.1} req/s", self.throughput());
+        println!(
+            "Status codes: {}",
+            if (self.statuses.is_empty()) {
+                "-".to_owned()
+            } else {
+                self.statuses
+                    .iter()
+                    .map(|(k, v)| format!("[{}] {}", k, v))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+        );
+        println!("Errors:       {}", self.errors.values().sum::<u64>());
+        for (k, v) in &self.errors {
+            println!("  [{}] {}", v, k);
+        }
+
+        if (self.latencies.is_empty()) {
+            return;
+        }
+
+        println!("\nLatency:");
+        println!("  min   {}", format_duration(self.percentile(0.0)));
+        println!(
+            "  mean  {}",
+            format_duration(Duration::from_micros(self.latencies.mean() as u64))
+        );
+        for q in [50.0, 90.0, 99.0] {
+            println!("  p{:<5}{}", q, format_duration(self.percentile(q)));
+        }
+        println!("  max   {}", format_duration(self.percentile(100.0)));
+
+        println!("\nHistogram:");
+        let min = self.latencies.min();
+        let max = self.latencies.max();
+        let width = ((max - min) / HISTOGRAM_BUCKETS).max(1);
+        let buckets = (0..HISTOGRAM_BUCKETS)
+            .map(|i| {
+                let low = min + width * i;
+                let high = if (i == HISTOGRAM_BUCKETS - 1) {
+                    max
+                } else {
+                    low + width - 1
+                };
+                (low, self.latencies.count_between(low, high))
+            })
+            .collect::<Vec<_>>();
+        let max_count = buckets.iter().map(|(_, c)| *c).max().unwrap_or(1).max(1);
+        for (low, count) in buckets {
+            println!(
+                "  {:>10} [{:>6}] {}",
+                format_duration(Duration::from_micros(low)),
+                count,
+                "■".repeat((count * HISTOGRAM_WIDTH / max_count) as usize)
+            );
+        }
+    }
+}
+
+//sends the request of `client` repeatedly, at most `options.concurrency` at a time
+//Sending stops when `options.requests` requests have been sent or `options.duration` has elapsed, whichever comes first.
+pub async fn run(client: &Client, options: Options) -> Result<Report, Error> {
+    let limit = match (options.requests, options.duration) {
+        (None, None) => Some(DEFAULT_REQUESTS),
+        (n, _) => n,
+    };
+    let sent = Arc::new(AtomicU64::new(0));
+    let start = Instant::now();
+    let deadline = options.duration.map(|d| start + d);
+
+    let mut workers = vec![];
+    for _ in 0..options.concurrency {
+        let client = client
+            .try_clone()
+            .ok_or_else(|| Error::Other("the request cannot be cloned".to_owned()))?;
+        let sent = sent.clone();
+        workers.push(tokio::spawn(async move {
+            let mut report = Report::new(options.concurrency);
+            loop {
+                if let Some(n) = limit {
+                    if (sent.fetch_add(1, Ordering::Relaxed) >= n) {
+                        break;
+                    }
+                }
+                if let Some(deadline) = deadline {
+                    if (Instant::now() >= deadline) {
+                        break;
+                    }
+                }
+                let c = client.try_clone().unwrap();
+                match (c.send().await) {
+                    Ok(response) => {
+                        *report.statuses.entry(response.status.as_u16()).or_default() += 1;
+                        report
+                            .latencies
+                            .saturating_record(response.timings.total().as_micros() as u64);
+                    }
+                    Err(e) => *report.errors.entry(e.to_string()).or_default() += 1,
+                }
+            }
+            report
+        }));
+    }
+
+    let mut ret = Report::new(options.concurrency);
+    for worker in workers {
+        ret.merge(worker.await.map_err(|e| Error::Other(e.to_string()))?);
+    }
+    ret.elapsed = start.elapsed();
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests_bench {
+    //{{{
+    use super::*;
+
+    use std::{
+        fs,
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use super::super::config::Config;
+
+    //serves `200 OK` forever, returning `500` for every fifth connection
+    fn serve() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for (i, stream) in listener.incoming().enumerate() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while (reader.read_line(&mut line).unwrap() > 2) {
+                    line.clear();
+                }
+                let status = if (i % 5 == 4) {
+                    "500 Internal Server Error"
+                } else {
+                    "200 OK"
+                };
+                let _ = stream.write_all(
+                    format!(
+                        "HTTP/1.1 {}\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+                        status
+                    )
+                    .as_bytes(),
+                );
+            }
+        });
+        base_url
+    }
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let dir = std::env::temp_dir().join(format!("ycurl_tests_bench_01_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("ycurl.json"),
+            format!(
+                r#"{{"base_url": "{}", "requests": [{{"name": "a", "url": "/", "method": "GET"}}]}}"#,
+                serve()
+            ),
+        )
+        .unwrap();
+        let config = Config::new(&dir.join("ycurl.json").to_string_lossy(), None).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+        let client = Client::new(&config, config.request("a").unwrap()).unwrap();
+
+        let options = Options {
+            requests: Some(50),
+            concurrency: 4,
+            duration: None,
+        };
+        let report = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(run(&client, options))
+            .unwrap();
+        report.print();
+        assert_eq!(50, report.total());
+        assert_eq!(Some(&40), report.statuses.get(&200));
+        assert_eq!(Some(&10), report.statuses.get(&500));
+        assert!(report.errors.is_empty());
+        assert_eq!(50, report.latencies.len());
+        assert!(Duration::ZERO < report.percentile(50.0));
+        assert!(report.percentile(50.0) <= report.percentile(99.0));
+
+        let options = Options {
+            requests: None,
+            concurrency: 2,
+            duration: Some(Duration::from_millis(200)),
+        };
+        let report = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(run(&client, options))
+            .unwrap();
+        assert!(report.total() > 0);
+        assert!(report.elapsed >= Duration::from_millis(200));
+    }
+    //}}}
+}
@@ -1,26 +1,200 @@
-use std::{collections::HashMap, error::Error};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use itertools::Itertools;
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
+use futures::stream::{self, BoxStream, StreamExt};
+use indicatif::{ProgressBar, ProgressStyle};
+use mime::Mime;
 use reqwest::{
-    blocking::Response,
-    header::{HeaderMap, HeaderName},
+    header::{HeaderMap, HeaderName, HeaderValue, ACCEPT_ENCODING, CONTENT_TYPE},
     redirect::Policy,
+    Method, StatusCode, Url, Version,
 };
+use serde::de::DeserializeOwned;
 use serde_json::Value;
+use tokio::io::AsyncWriteExt;
+
+use super::compression::{self, Decoder, Encoding};
+use super::config::{Config, HTTPMethod, HttpVersion, Request};
+use super::error::Error;
+use super::graphql;
+use super::redirect::{self, Chain, Redirect};
+use super::render;
+use super::timing::Recorder;
+
+pub const UNIX_SOCKET_SCHEME: &str = "unix://";
 
-use super::config::{Config, HTTPMethod, Request};
-use super::logger::Logger;
+//the size from which a progress bar is shown while a response body is saved to a file
+const PROGRESS_THRESHOLD: u64 = 1024 * 1024;
+
+//the number of bytes of a streaming response body kept after the events are printed
+pub const MAX_STREAMED_BODY: usize = 64 * 1024;
 
 pub struct Client {
-    client: reqwest::blocking::RequestBuilder,
+    name: String,
+    client: reqwest::RequestBuilder,
+    recorder: Recorder,
+    chain: Chain,
+    save_to: Option<PathBuf>,
+    progress: bool,
+    //whether to decode a compressed body
+    compressed: bool,
+}
+
+//the order in which `send_all()` yields the results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Order {
+    //the order of the given clients
+    Definition,
+    //the order in which the responses are received
+    Completion,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Timings {
+    //the time taken for DNS resolution (`None` if not performed, e.g. the host is an IP address)
+    pub dns: Option<Duration>,
+    //the time taken for TCP connection and TLS handshake (`None` if not performed, e.g. a connection is reused)
+    pub connect: Option<Duration>,
+    //the time until the response headers are received (i.e. time to first byte), including `dns` and `connect`
+    pub wait: Duration,
+    //the time taken to read the response body
+    pub receive: Duration,
+    //whether other requests were in flight at the same time (see `send_all()`), in which case the durations may include the time spent on them
+    pub concurrent: bool,
+}
+
+impl Timings {
+    pub fn total(&self) -> Duration {
+        self.wait + self.receive
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub started: DateTime<Local>,
+    //the final URL after redirects
+    pub url: Url,
+    //the redirects followed, in order
+    pub redirects: Vec<Redirect>,
+    pub status: StatusCode,
+    pub version: Version,
+    pub headers: HeaderMap,
+    //empty if the body is saved to a file
+    pub body: Vec<u8>,
+    //the number of bytes dropped from the beginning of `body` to keep only the last `MAX_STREAMED_BODY` bytes of a streaming response
+    pub dropped: u64,
+    pub saved: Option<Saved>,
+    //the coding and the size on the wire of a body decoded with `--compressed`
+    pub encoded: Option<Encoded>,
+    pub timings: Timings,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Encoded {
+    pub encoding: Encoding,
+    pub size: u64,
+}
+
+//a response body saved to a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Saved {
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+impl Response {
+    //the size of the body in bytes, including the one saved to a file
+    pub fn size(&self) -> u64 {
+        match (&self.saved) {
+            Some(saved) => saved.size,
+            None => self.body.len() as u64 + self.dropped,
+        }
+    }
+
+    pub fn content_type(&self) -> Option<Mime> {
+        render::content_type(&self.headers)
+    }
+
+    //decodes the body with the charset in `Content-Type` (UTF-8 by default), replacing invalid sequences
+    pub fn text(&self) -> String {
+        render::decode(&self.body, self.content_type().as_ref())
+    }
+
+    pub fn kind(&self) -> render::Kind {
+        render::kind(self.content_type().as_ref(), &self.body)
+    }
+
+    //renders the body for display according to `Content-Type`
+    pub fn render(&self) -> render::Rendered {
+        render::render(self.content_type().as_ref(), &self.body)
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
 }
 
-fn create_headermap(m: &HashMap<String, String>) -> HeaderMap {
+//`path` is the path of `m` in the config (e.g. `requests[0].headers`), which is used for error messages
+fn create_headermap(
+    m: &HashMap<String, String>,
+    request: Option<&str>,
+    path: &str,
+) -> Result<HeaderMap, Error> {
+    let error = |k: &str, message: String| Error::InvalidConfig {
+        message,
+        request: request.map(|s| s.to_owned()),
+        path: Some(format!("{}.{}", path, k)),
+    };
     let mut header = HeaderMap::new();
-    m.iter().for_each(|(k, v)| {
-        header.insert(k.parse::<HeaderName>().unwrap(), v.parse().unwrap());
-    });
-    header
+    for (k, v) in m {
+        let name = k
+            .parse::<HeaderName>()
+            .map_err(|_| error(k, format!("invalid header name: `{}`", k)))?;
+        let value = v
+            .parse::<HeaderValue>()
+            .map_err(|_| error(k, format!("invalid value for header `{}`: `{}`", k, v)))?;
+        header.insert(name, value);
+    }
+    Ok(header)
+}
+
+//whether `url` of a request is used as it is, without `base_url` prepended
+//It is so when it starts with one of the schemes `http://`, `https://`, `ws://` and `wss://` (case-insensitive), so that e.g. `/https_proxy` or `/wsdl` is a path.
+pub fn is_absolute_url(url: &str) -> bool {
+    match (url.split_once("://")) {
+        Some((scheme, _)) => ["http", "https", "ws", "wss"]
+            .iter()
+            .any(|s| scheme.eq_ignore_ascii_case(s)),
+        None => false,
+    }
+}
+
+#[cfg(unix)]
+fn unix_socket(
+    builder: reqwest::ClientBuilder,
+    path: &str,
+    _: &Request,
+) -> Result<reqwest::ClientBuilder, Error> {
+    Ok(builder.unix_socket(path))
+}
+
+#[cfg(not(unix))]
+fn unix_socket(
+    _: reqwest::ClientBuilder,
+    _: &str,
+    request: &Request,
+) -> Result<reqwest::ClientBuilder, Error> {
+    Err(Error::InvalidConfig {
+        message: "Unix domain sockets are not supported on this platform".to_owned(),
+        request: Some(request.name.clone()),
+        path: Some("base_url".to_owned()),
+    })
 }
 
 fn merge_headermap(m1: &mut HeaderMap, m2: &HeaderMap) {
@@ -30,49 +204,100 @@ fn merge_headermap(m1: &mut HeaderMap, m2: &HeaderMap) {
 }
 
 impl Client {
-    pub fn new(
-        config: &Config,
-        request: &Request,
-        logger: &mut Logger,
-    ) -> Result<Self, Box<dyn Error>> {
-        let url = if (request.url.starts_with("http")) {
-            request.url.clone()
+    pub fn new(config: &Config, request: &Request) -> Result<Self, Error> {
+        //`base_url` like `unix:///var/run/docker.sock` sends the requests over the Unix domain socket, with `localhost` as the host of the URL
+        let (base_url, socket) = match (config.base_url.strip_prefix(UNIX_SOCKET_SCHEME)) {
+            Some(path) => ("http://localhost", Some(path)),
+            None => (config.base_url.as_str(), None),
+        };
+        let (url, socket) = if (is_absolute_url(&request.url)) {
+            (request.url.clone(), None)
         } else {
-            format!("{}{}", config.base_url, request.url)
+            (format!("{}{}", base_url, request.url), socket)
         };
 
         let headers = {
-            let mut m1 = create_headermap(&config.default_headers);
-            let m2 = create_headermap(&request.headers);
+            let mut m1 = create_headermap(&config.default_headers, None, "default_headers")?;
+            let index = config
+                .requests
+                .iter()
+                .position(|r| r.name == request.name)
+                .unwrap_or_default();
+            let m2 = create_headermap(
+                &request.headers,
+                Some(&request.name),
+                &format!("requests[{}].headers", index),
+            )?;
             merge_headermap(&mut m1, &m2);
+            if (config.cli_options.compressed && !m1.contains_key(ACCEPT_ENCODING)) {
+                m1.insert(
+                    ACCEPT_ENCODING,
+                    HeaderValue::from_static(compression::ACCEPT_ENCODING),
+                );
+            }
             m1
         };
 
-        let client = reqwest::blocking::Client::builder()
+        let method = match (request.method) {
+            HTTPMethod::Get => Method::GET,
+            HTTPMethod::Post => Method::POST,
+            HTTPMethod::Put => Method::PUT,
+            HTTPMethod::Delete => Method::DELETE,
+            HTTPMethod::Patch => Method::PATCH,
+            HTTPMethod::Head => Method::HEAD,
+        };
+
+        let recorder = Recorder::default();
+        let chain = Chain::default();
+        let mut builder = reqwest::Client::builder()
+            .dns_resolver(Arc::new(recorder.clone()))
+            .connector_layer(recorder.clone())
             .redirect(if (config.cli_options.disable_redirect) {
                 Policy::none()
             } else {
-                Policy::limited(10)
-            })
-            .build()?;
-
-        let mut client = match (request.method) {
-            HTTPMethod::Get => client.get(url),
-            HTTPMethod::Post => client.post(url),
-            HTTPMethod::Put => client.put(url),
-            HTTPMethod::Delete => client.delete(url),
-            HTTPMethod::Patch => client.patch(url),
-            HTTPMethod::Head => client.head(url),
+                chain.policy(
+                    method.clone(),
+                    config
+                        .cli_options
+                        .max_redirects
+                        .unwrap_or(redirect::DEFAULT_MAX_REDIRECTS),
+                    config.cli_options.same_host_redirects,
+                )
+            });
+        builder = match (config.cli_options.http_version) {
+            HttpVersion::Auto => builder,
+            HttpVersion::Http1 => builder.http1_only(),
+            HttpVersion::Http2 => builder.http2_prior_knowledge(),
         };
+        if let Some(path) = socket {
+            builder = unix_socket(builder, path, request)?;
+        }
+        let client = builder.build().map_err(|e| Error::Http {
+            request: request.name.clone(),
+            source: e,
+        })?;
+
+        let mut client = client.request(method, url);
 
         client = client.headers(headers.clone()).query(&request.params);
+        if let Some(graphql) = &request.graphql {
+            //`Content-Type` in `headers`, if any, takes precedence
+            if (!headers.contains_key(CONTENT_TYPE)) {
+                client = client.header(CONTENT_TYPE, "application/json");
+            }
+            client = client.body(serde_json::to_string(&graphql::envelope(graphql)?)?);
+        }
         if let Some(b) = &request.body {
             match b {
                 Value::String(s) => client = client.body(s.to_owned()),
                 Value::Object(o) => {
                     let mut is_set = false;
                     if let Some(v) = headers.get("content-type") {
-                        if (v.to_str()?.contains("application/x-www-form-urlencoded")) {
+                        if (v
+                            .to_str()
+                            .unwrap_or_default()
+                            .contains("application/x-www-form-urlencoded"))
+                        {
                             client = client.form(o);
                             is_set = true;
                         }
@@ -84,42 +309,901 @@ impl Client {
                 _ => unreachable!(),
             }
         }
+        if let Some(b) = &request.raw_body {
+            client = client.body(b.clone());
+        }
 
-        if (config.cli_options.verbose) {
-            if (request.params.is_empty()) {
-                println!("{}\n", request.url);
-            } else {
-                let query_parameters = request
-                    .params
-                    .iter()
-                    .map(|(k, v)| format!("{}={}", k, v.to_string().trim_matches('"')))
-                    .join("&");
-                println!("{}?{}\n", request.url, query_parameters);
+        Ok(Self {
+            name: request.name.clone(),
+            client,
+            recorder,
+            chain,
+            save_to: request.save_to.as_ref().map(PathBuf::from),
+            progress: false,
+            compressed: config.cli_options.compressed,
+        })
+    }
+
+    //overrides `save_to` of the request
+    pub fn save_to(mut self, path: Option<PathBuf>) -> Self {
+        self.save_to = path;
+        self
+    }
+
+    //shows a progress bar on stderr while a large body is saved to a file
+    pub fn progress(mut self, enabled: bool) -> Self {
+        self.progress = enabled;
+        self
+    }
+
+    //The returned client shares the connection pool with `self`.
+    //`Timings::dns`, `Timings::connect` and `Response::redirects` are not reliable when the clones send requests concurrently.
+    pub fn try_clone(&self) -> Option<Self> {
+        Some(Self {
+            name: self.name.clone(),
+            client: self.client.try_clone()?,
+            recorder: self.recorder.clone(),
+            chain: self.chain.clone(),
+            save_to: self.save_to.clone(),
+            progress: self.progress,
+            compressed: self.compressed,
+        })
+    }
+
+    //returns a copy of the request to be sent (used e.g. for HAR export)
+    pub fn request(&self) -> Option<reqwest::Request> {
+        self.client.try_clone()?.build().ok()
+    }
+
+    pub async fn send(self) -> Result<Response, Error> {
+        self.send_head().await?.read().await
+    }
+
+    //sends the request and returns as soon as the response headers are received
+    pub async fn send_head(self) -> Result<Head, Error> {
+        self.recorder.take();
+        self.chain.take();
+        let started = Local::now();
+        let start = Instant::now();
+        let res = self.client.send().await.map_err(|e| Error::Http {
+            request: self.name.clone(),
+            source: e,
+        })?;
+        let wait = start.elapsed();
+        let recorded = self.recorder.take();
+        let response = Response {
+            started,
+            url: res.url().clone(),
+            redirects: self.chain.take(),
+            status: res.status(),
+            version: res.version(),
+            headers: res.headers().clone(),
+            body: vec![],
+            dropped: 0,
+            saved: None,
+            encoded: None,
+            timings: Timings {
+                dns: recorded.dns,
+                connect: recorded.connect,
+                wait,
+                receive: Duration::ZERO,
+                concurrent: false,
+            },
+        };
+        let encoding = if (self.compressed) {
+            Encoding::of(&response.headers).map_err(|encoding| Error::Decode {
+                request: self.name.clone(),
+                encoding,
+                source: std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "unknown or stacked content codings are not decoded",
+                ),
+            })?
+        } else {
+            None
+        };
+        Ok(Head {
+            name: self.name,
+            res,
+            start,
+            response,
+            save_to: self.save_to,
+            progress: self.progress,
+            encoding,
+        })
+    }
+
+    //sends the request on a new single-threaded runtime, which is used only by the tests as the binary runs everything on the runtime of `main()`
+    //This shall not be called inside an async context.
+    #[cfg(test)]
+    pub fn send_blocking(self) -> Result<Response, Error> {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| Error::Other(e.to_string()))?
+            .block_on(self.send())
+    }
+}
+
+enum SaveError {
+    Http(reqwest::Error),
+    Io(std::io::Error),
+    Decode(std::io::Error),
+}
+
+//writes the body to `path` chunk by chunk without buffering the whole of it, decoding it if `decoder` is set
+//Returns the number of bytes written and the number of bytes received. The file is removed if the download fails halfway.
+async fn save(
+    mut res: reqwest::Response,
+    path: &Path,
+    progress: bool,
+    mut decoder: Option<Decoder>,
+) -> Result<(u64, u64), SaveError> {
+    let bar = match (res.content_length()) {
+        _ if (!progress) => ProgressBar::hidden(),
+        Some(n) if (n < PROGRESS_THRESHOLD) => ProgressBar::hidden(),
+        Some(n) => ProgressBar::new(n).with_style(
+            ProgressStyle::with_template(
+                "[{bar:40}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})",
+            )
+            .unwrap()
+            .progress_chars("=> "),
+        ),
+        None => ProgressBar::new_spinner().with_style(
+            ProgressStyle::with_template("{spinner} {bytes} ({bytes_per_sec})").unwrap(),
+        ),
+    };
+
+    let mut file = tokio::fs::File::create(path).await.map_err(SaveError::Io)?;
+    let mut size = 0;
+    let mut received = 0;
+    let result = async {
+        while let Some(chunk) = res.chunk().await.map_err(SaveError::Http)? {
+            received += chunk.len() as u64;
+            bar.set_position(received);
+            let chunk = match (&mut decoder) {
+                Some(d) => d.push(&chunk).map_err(SaveError::Decode)?,
+                None => chunk.to_vec(),
+            };
+            file.write_all(&chunk).await.map_err(SaveError::Io)?;
+            size += chunk.len() as u64;
+        }
+        if let Some(d) = decoder.take() {
+            let rest = d.finish().map_err(SaveError::Decode)?;
+            file.write_all(&rest).await.map_err(SaveError::Io)?;
+            size += rest.len() as u64;
+        }
+        file.flush().await.map_err(SaveError::Io)
+    }
+    .await;
+    bar.finish_and_clear();
+
+    if (result.is_err()) {
+        let _ = tokio::fs::remove_file(path).await;
+    }
+    result.map(|_| (size, received))
+}
+
+//a response whose headers have been received but whose body has not been read yet
+pub struct Head {
+    name: String,
+    res: reqwest::Response,
+    start: Instant,
+    //the response without the body
+    response: Response,
+    save_to: Option<PathBuf>,
+    progress: bool,
+    //the coding of a body to decode
+    encoding: Option<Encoding>,
+}
+
+impl Head {
+    //the response with an empty body
+    pub fn response(&self) -> &Response {
+        &self.response
+    }
+
+    //whether the body is going to be saved to a file
+    pub fn is_saved(&self) -> bool {
+        self.save_to.is_some()
+    }
+
+    fn error(&self, e: reqwest::Error) -> Error {
+        Error::Http {
+            request: self.name.clone(),
+            source: e,
+        }
+    }
+
+    fn decode_error(&self, encoding: Encoding, e: std::io::Error) -> Error {
+        Error::Decode {
+            request: self.name.clone(),
+            encoding: encoding.as_str().to_owned(),
+            source: e,
+        }
+    }
+
+    fn decoder(&self) -> Result<Option<Decoder>, Error> {
+        self.encoding
+            .map(|encoding| Decoder::new(encoding).map_err(|e| self.decode_error(encoding, e)))
+            .transpose()
+    }
+
+    //reads the whole body, saving it to a file if `save_to` is set
+    pub async fn read(self) -> Result<Response, Error> {
+        let decoder = self.decoder()?;
+        let mut response = self.response;
+        let received = match (&self.save_to) {
+            Some(path) => {
+                let (size, received) =
+                    save(self.res, path, self.progress, decoder)
+                        .await
+                        .map_err(|e| match (e) {
+                            SaveError::Http(e) => Error::Http {
+                                request: self.name.clone(),
+                                source: e,
+                            },
+                            SaveError::Io(e) => Error::Io {
+                                path: path.clone(),
+                                source: e,
+                            },
+                            SaveError::Decode(e) => Error::Decode {
+                                request: self.name.clone(),
+                                encoding: self
+                                    .encoding
+                                    .map(|e| e.as_str().to_owned())
+                                    .unwrap_or_default(),
+                                source: e,
+                            },
+                        })?;
+                response.saved = Some(Saved {
+                    path: path.clone(),
+                    size,
+                });
+                received
+            }
+            None => {
+                let bytes = self.res.bytes().await.map_err(|e| Error::Http {
+                    request: self.name.clone(),
+                    source: e,
+                })?;
+                response.body = match (self.encoding) {
+                    Some(encoding) => {
+                        compression::decode(encoding, &bytes).map_err(|e| Error::Decode {
+                            request: self.name.clone(),
+                            encoding: encoding.as_str().to_owned(),
+                            source: e,
+                        })?
+                    }
+                    None => bytes.to_vec(),
+                };
+                bytes.len() as u64
+            }
+        };
+        //an empty body (e.g. of `HEAD`) is not regarded as encoded
+        response.encoded = self
+            .encoding
+            .filter(|_| (received != 0))
+            .map(|encoding| Encoded {
+                encoding,
+                size: received,
+            });
+        response.timings.receive = self.start.elapsed() - response.timings.wait;
+        Ok(response)
+    }
+
+    //reads the body chunk by chunk, passing each (decoded) chunk to `f` as it arrives
+    //Reading stops when `f` returns `false` or an error. Only the last `MAX_STREAMED_BODY` bytes read are kept in the body of the returned response so that a long-lived stream does not use up memory.
+    pub async fn read_chunks(
+        mut self,
+        mut f: impl FnMut(&[u8]) -> Result<bool, Error>,
+    ) -> Result<Response, Error> {
+        let mut decoder = self.decoder()?;
+        let mut received = 0;
+        loop {
+            let chunk = match (self.res.chunk().await) {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                Err(e) => return Err(self.error(e)),
+            };
+            received += chunk.len() as u64;
+            let chunk = match (&mut decoder) {
+                Some(d) => d
+                    .push(&chunk)
+                    .map_err(|e| self.decode_error(self.encoding.unwrap(), e))?,
+                None => chunk.to_vec(),
+            };
+            self.keep(&chunk);
+            if (!f(&chunk)?) {
+                //the rest of the body is not decoded
+                decoder = None;
+                break;
             }
         }
+        if let Some(d) = decoder {
+            let rest = d
+                .finish()
+                .map_err(|e| self.decode_error(self.encoding.unwrap(), e))?;
+            if (!rest.is_empty()) {
+                self.keep(&rest);
+                f(&rest)?;
+            }
+        }
+        self.response.encoded = self
+            .encoding
+            .filter(|_| (received != 0))
+            .map(|encoding| Encoded {
+                encoding,
+                size: received,
+            });
+        self.response.timings.receive = self.start.elapsed() - self.response.timings.wait;
+        Ok(self.response)
+    }
 
-        logger.log("[request]\n")?;
-        if let Some(rb) = client.try_clone() {
-            if let Ok(req) = rb.build() {
-                logger.log(&format!("method: {}\n", req.method()))?;
-                logger.log(&format!("url: {}\n", req.url().as_str()))?;
-                logger.log(&format!("headers: {:?}\n", headers))?;
-            } else {
-                logger.log(&format!("request: {:?}\n", client))?;
+    //appends `chunk` to the body, dropping the oldest bytes beyond `MAX_STREAMED_BODY`
+    fn keep(&mut self, chunk: &[u8]) {
+        let body = &mut self.response.body;
+        body.extend_from_slice(chunk);
+        if (body.len() > MAX_STREAMED_BODY) {
+            let excess = body.len() - MAX_STREAMED_BODY;
+            body.drain(..excess);
+            self.response.dropped += excess as u64;
+        }
+    }
+}
+
+//sends the requests concurrently, at most `concurrency` at a time, and yields the results with the indices in `clients`
+pub fn send_all(
+    clients: Vec<Client>,
+    concurrency: usize,
+    order: Order,
+) -> BoxStream<'static, (usize, Result<Response, Error>)> {
+    let concurrent = (concurrency > 1 && clients.len() > 1);
+    let futures = stream::iter(clients.into_iter().enumerate().map(
+        move |(i, client)| async move {
+            let result = client.send().await.map(|mut response| {
+                response.timings.concurrent = concurrent;
+                response
+            });
+            (i, result)
+        },
+    ));
+    match (order) {
+        Order::Definition => futures.buffered(concurrency).boxed(),
+        Order::Completion => futures.buffer_unordered(concurrency).boxed(),
+    }
+}
+
+#[cfg(test)]
+mod tests_client {
+    //{{{
+    use super::*;
+
+    use std::{
+        fs,
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    //serves a single canned response and returns the raw request received
+    fn serve_once(response: impl Into<Vec<u8>>) -> (String, thread::JoinHandle<String>) {
+        let response = response.into();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = String::new();
+            let mut buf = [0; 4096];
+            //reads until the end of the body
+            loop {
+                let n = stream.read(&mut buf).unwrap();
+                received.push_str(&String::from_utf8_lossy(&buf[..n]));
+                if let Some((headers, body)) = received.split_once("\r\n\r\n") {
+                    let content_length = headers
+                        .lines()
+                        .find_map(|l| {
+                            l.to_lowercase()
+                                .strip_prefix("content-length: ")
+                                .map(|v| v.parse::<usize>().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if (n == 0 || body.len() >= content_length) {
+                        break;
+                    }
+                }
             }
-        } else {
-            logger.log(&format!("request: {:?}\n", client))?;
+            stream.write_all(&response).unwrap();
+            received
+        });
+        (base_url, handle)
+    }
+
+    //returns an empty directory for the test `name` (e.g. `01`), which the test removes
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "ycurl_tests_client_{}_{}",
+            name,
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    //writes `content` to `ycurl.json` in `dir` and loads it
+    fn write_config(dir: &Path, content: &str) -> Config {
+        fs::write(dir.join("ycurl.json"), content).unwrap();
+        Config::new(&dir.join("ycurl.json").to_string_lossy(), None).unwrap()
+    }
+
+    //loads `content` as a config file, leaving nothing on disk
+    fn load_config(name: &str, content: &str) -> Config {
+        let dir = temp_dir(name);
+        let ret = write_config(&dir, content);
+        fs::remove_dir_all(&dir).unwrap();
+        ret
+    }
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let (base_url, handle) = serve_once(
+            "HTTP/1.1 201 Created\r\nContent-Type: application/json\r\nContent-Length: 13\r\nConnection: close\r\n\r\n{\"id\": \"abc\"}"
+                .to_owned(),
+        );
+        let config = load_config(
+            "01",
+            &format!(
+                r#"{{
+                    "base_url": "{}",
+                    "variables": {{"name": "Mike"}},
+                    "requests": [
+                        {{
+                            "name": "create_user",
+                            "url": "/users",
+                            "method": "POST",
+                            "headers": {{"Content-Type": "application/json"}},
+                            "body": {{"name": "${{name}}"}}
+                        }}
+                    ]
+                }}"#,
+                base_url
+            ),
+        );
+        let request = config.request("create_user").unwrap();
+        let response = Client::new(&config, request)
+            .unwrap()
+            .send_blocking()
+            .unwrap();
+
+        let received = handle.join().unwrap();
+        assert!(received.starts_with("POST /users HTTP/1.1\r\n"));
+        assert!(received.contains("\"name\": \"Mike\""));
+
+        assert_eq!(StatusCode::CREATED, response.status);
+        assert_eq!("application/json", response.headers["content-type"]);
+        assert_eq!(b"{\"id\": \"abc\"}".to_vec(), response.body);
+        assert_eq!(
+            "abc",
+            response.json::<Value>().unwrap()["id"].as_str().unwrap()
+        );
+        assert_eq!(
+            response.timings.wait + response.timings.receive,
+            response.timings.total()
+        );
+        //DNS resolution is skipped for an IP address
+        assert_eq!(None, response.timings.dns);
+        assert!(response.timings.connect.unwrap() <= response.timings.wait);
+
+        assert!(matches!(
+            config.request("1"),
+            Err(Error::RequestNotFound(_))
+        ));
+        assert_eq!("create_user", config.request("0").unwrap().name);
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        let servers = ["200 OK", "404 Not Found", "500 Internal Server Error"].map(|status| {
+            serve_once(format!(
+                "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                status
+            ))
+        });
+        let config = load_config(
+            "02",
+            &format!(
+                r#"{{
+                    "base_url": "http://example.com",
+                    "requests": [
+                        {{"name": "a", "url": "{}/", "method": "GET", "group": "g"}},
+                        {{"name": "b", "url": "{}/", "method": "GET", "group": "g"}},
+                        {{"name": "c", "url": "{}/", "method": "GET"}},
+                        {{"name": "d", "url": "/", "method": "GET", "group": "g"}}
+                    ]
+                }}"#,
+                servers[0].0, servers[1].0, servers[2].0
+            ),
+        );
+
+        assert_eq!(
+            vec!["a", "b", "d"],
+            config
+                .group("g")
+                .unwrap()
+                .iter()
+                .map(|r| &r.name)
+                .collect::<Vec<_>>()
+        );
+        assert!(matches!(config.group("x"), Err(Error::GroupNotFound(_))));
+
+        let clients = ["a", "b", "c"]
+            .iter()
+            .map(|name| Client::new(&config, config.request(name).unwrap()).unwrap())
+            .collect::<Vec<_>>();
+        let results = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(send_all(clients, 2, Order::Definition).collect::<Vec<_>>());
+        assert_eq!(
+            vec![
+                (0, StatusCode::OK),
+                (1, StatusCode::NOT_FOUND),
+                (2, StatusCode::INTERNAL_SERVER_ERROR)
+            ],
+            results
+                .iter()
+                .map(|(i, r)| (*i, r.as_ref().unwrap().status))
+                .collect::<Vec<_>>()
+        );
+        //the durations are marked as measured concurrently
+        assert!(results
+            .iter()
+            .all(|(_, r)| r.as_ref().unwrap().timings.concurrent));
+        for (_, handle) in servers {
+            handle.join().unwrap();
         }
-        if let Some(body) = &request.body {
-            logger.log(&format!("body: {:?}", body))?;
-        } else {
-            logger.log("body: None")?;
+    }
+
+    #[test]
+    // #[ignore]
+    fn test03() {
+        let (base_url, handle) = serve_once(
+            "HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nContent-Length: 7\r\nConnection: close\r\n\r\n\u{0089}PNG\r\n"
+                .to_owned(),
+        );
+        let dir = temp_dir("03");
+        let config = write_config(
+            &dir,
+            &format!(
+                r#"{{
+                    "base_url": "{}",
+                    "variables": {{"dir": "{}"}},
+                    "requests": [
+                        {{"name": "image", "url": "/image.png", "method": "GET", "save_to": "${{dir}}/image.png"}}
+                    ]
+                }}"#,
+                base_url,
+                dir.display()
+            ),
+        );
+        let request = config.request("image").unwrap();
+        let response = Client::new(&config, request)
+            .unwrap()
+            .send_blocking()
+            .unwrap();
+        handle.join().unwrap();
+        let saved = fs::read(dir.join("image.png")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(response.body.is_empty());
+        assert_eq!(
+            Some(Saved {
+                path: dir.join("image.png"),
+                size: 7,
+            }),
+            response.saved
+        );
+        assert_eq!(7, response.size());
+        assert_eq!("\u{0089}PNG\r\n".as_bytes(), saved);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    // #[ignore]
+    fn test04() {
+        use std::os::unix::net::UnixListener;
+
+        let dir = temp_dir("04");
+        let socket = dir.join("ycurl.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 4096];
+            let n = stream.read(&mut buf).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n[]")
+                .unwrap();
+            String::from_utf8_lossy(&buf[..n]).into_owned()
+        });
+        let config = write_config(
+            &dir,
+            &format!(
+                r#"{{
+                    "base_url": "unix://{}",
+                    "requests": [
+                        {{"name": "containers", "url": "/containers/json", "method": "GET", "params": {{"all": true}}}}
+                    ]
+                }}"#,
+                socket.display()
+            ),
+        );
+        let request = config.request("containers").unwrap();
+        let response = Client::new(&config, request)
+            .unwrap()
+            .send_blocking()
+            .unwrap();
+        let received = handle.join().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(received.starts_with("GET /containers/json?all=true HTTP/1.1\r\n"));
+        assert!(received.to_lowercase().contains("host: localhost\r\n"));
+        assert_eq!(StatusCode::OK, response.status);
+        assert_eq!(b"[]", response.body.as_slice());
+    }
+
+    #[test]
+    // #[ignore]
+    fn test05() {
+        let body = "hello, world\n".repeat(100);
+        let gzipped = {
+            let mut e = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+            e.write_all(body.as_bytes()).unwrap();
+            e.finish().unwrap()
+        };
+        let servers = [(); 2].map(|_| {
+            let mut response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                gzipped.len()
+            )
+            .into_bytes();
+            response.extend(&gzipped);
+            serve_once(response)
+        });
+        let dir = temp_dir("05");
+        let config = write_config(
+            &dir,
+            &format!(
+                r#"{{
+                    "base_url": "http://example.com",
+                    "cli_options": {{"compressed": true}},
+                    "requests": [
+                        {{"name": "a", "url": "{}/", "method": "GET"}},
+                        {{"name": "b", "url": "{}/", "method": "GET", "save_to": "{}"}}
+                    ]
+                }}"#,
+                servers[0].0,
+                servers[1].0,
+                dir.join("b.txt").display()
+            ),
+        );
+
+        let expected = Some(Encoded {
+            encoding: Encoding::Gzip,
+            size: gzipped.len() as u64,
+        });
+        let response = Client::new(&config, config.request("a").unwrap())
+            .unwrap()
+            .send_blocking()
+            .unwrap();
+        assert_eq!(body.as_bytes(), response.body.as_slice());
+        assert_eq!(expected, response.encoded);
+
+        let response = Client::new(&config, config.request("b").unwrap())
+            .unwrap()
+            .send_blocking()
+            .unwrap();
+        assert_eq!(body, fs::read_to_string(dir.join("b.txt")).unwrap());
+        assert_eq!(body.len() as u64, response.size());
+        assert_eq!(expected, response.encoded);
+        fs::remove_dir_all(&dir).unwrap();
+
+        for (_, handle) in servers {
+            let received = handle.join().unwrap();
+            assert!(received
+                .to_lowercase()
+                .contains("accept-encoding: gzip, deflate, br, zstd\r\n"));
         }
+    }
+
+    #[test]
+    // #[ignore]
+    fn test06() {
+        let redirect = |status: &str, location: &str| {
+            format!(
+                "HTTP/1.1 {}\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                status, location
+            )
+        };
+        //port 1 is never connected to as the redirect to it is not followed
+        let unreachable = "http://127.0.0.1:1/x";
+
+        let c = serve_once("HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok");
+        let b = serve_once(redirect("302 Found", &format!("{}/c", c.0)));
+        let a = serve_once(redirect("307 Temporary Redirect", &format!("{}/b", b.0)));
+        let d = serve_once(redirect("307 Temporary Redirect", unreachable));
+        let f = serve_once(redirect("302 Found", unreachable));
+        let e = serve_once(redirect("307 Temporary Redirect", &format!("{}/f", f.0)));
+
+        let mut config = load_config(
+            "06",
+            &format!(
+                r#"{{
+                    "base_url": "http://example.com",
+                    "requests": [
+                        {{"name": "a", "url": "{}/a", "method": "POST", "body": "hello"}},
+                        {{"name": "d", "url": "{}/d", "method": "POST", "body": "hello"}},
+                        {{"name": "e", "url": "{}/e", "method": "POST", "body": "hello"}}
+                    ]
+                }}"#,
+                a.0, d.0, e.0
+            ),
+        );
+        let send = |config: &Config, name: &str| {
+            Client::new(config, config.request(name).unwrap())
+                .unwrap()
+                .send_blocking()
+        };
+
+        //`307` preserves the method and the body while `302` changes `POST` to `GET`
+        let response = send(&config, "a").unwrap();
+        assert_eq!(StatusCode::OK, response.status);
+        assert_eq!(
+            vec![
+                (
+                    StatusCode::TEMPORARY_REDIRECT,
+                    Method::POST,
+                    format!("{}/b", b.0)
+                ),
+                (StatusCode::FOUND, Method::GET, format!("{}/c", c.0)),
+            ],
+            response
+                .redirects
+                .iter()
+                .map(|r| (r.status, r.method.clone(), r.to.to_string()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(format!("{}/a", a.0), response.redirects[0].from.as_str());
+        assert!(a.1.join().unwrap().starts_with("POST /a HTTP/1.1\r\n"));
+        let received = b.1.join().unwrap();
+        assert!(received.starts_with("POST /b HTTP/1.1\r\n"));
+        assert!(received.ends_with("\r\n\r\nhello"));
+        assert!(c.1.join().unwrap().starts_with("GET /c HTTP/1.1\r\n"));
+
+        //the redirect to another port is not followed
+        config.cli_options.same_host_redirects = true;
+        let response = send(&config, "d").unwrap();
+        assert_eq!(StatusCode::TEMPORARY_REDIRECT, response.status);
+        assert!(response.redirects.is_empty());
+        d.1.join().unwrap();
+
+        config.cli_options.same_host_redirects = false;
+        config.cli_options.max_redirects = Some(1);
+        let Err(error) = send(&config, "e") else {
+            panic!();
+        };
+        assert!(error.to_string().contains(&format!(
+            "too many redirects (max: 1): {}/e -> {}/f -> {}",
+            e.0, f.0, unreachable
+        )));
+        e.1.join().unwrap();
+        f.1.join().unwrap();
+    }
+
+    #[test]
+    // #[ignore]
+    fn test07() {
+        //only the last part of a long streaming body is kept
+        let line = format!("{{\"data\": \"{}\"}}\n", "x".repeat(1000));
+        let body = line.repeat(MAX_STREAMED_BODY / line.len() * 2);
+        let (base_url, handle) = serve_once(format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/x-ndjson\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        ));
+        let config = load_config(
+            "07",
+            &format!(
+                r#"{{"base_url": "{}", "requests": [{{"name": "a", "url": "/", "method": "GET"}}]}}"#,
+                base_url
+            ),
+        );
+
+        let client = Client::new(&config, config.request("a").unwrap()).unwrap();
+        let mut read = 0;
+        let response = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(async {
+                client
+                    .send_head()
+                    .await?
+                    .read_chunks(|chunk| {
+                        read += chunk.len();
+                        Ok(true)
+                    })
+                    .await
+            })
+            .unwrap();
+        handle.join().unwrap();
+        assert_eq!(body.len(), read);
+        assert_eq!(MAX_STREAMED_BODY, response.body.len());
+        assert!(body.as_bytes().ends_with(&response.body));
+        assert_eq!(body.len() as u64, response.size());
+    }
+
+    #[test]
+    // #[ignore]
+    fn test08() {
+        //an empty body with `Content-Encoding` under `--compressed`
+        let head = serve_once(
+            "HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nContent-Length: 20\r\nConnection: close\r\n\r\n",
+        );
+        let no_content = serve_once(
+            "HTTP/1.1 204 No Content\r\nContent-Encoding: gzip\r\nConnection: close\r\n\r\n",
+        );
+        let stacked = serve_once(
+            "HTTP/1.1 200 OK\r\nContent-Encoding: gzip, br\r\nContent-Length: 3\r\nConnection: close\r\n\r\nabc",
+        );
+        let config = load_config(
+            "08",
+            &format!(
+                r#"{{
+                    "base_url": "http://example.com",
+                    "cli_options": {{"compressed": true}},
+                    "requests": [
+                        {{"name": "head", "url": "{}/", "method": "HEAD"}},
+                        {{"name": "no_content", "url": "{}/", "method": "DELETE"}},
+                        {{"name": "stacked", "url": "{}/", "method": "GET"}}
+                    ]
+                }}"#,
+                head.0, no_content.0, stacked.0
+            ),
+        );
+        let send = |name: &str| {
+            Client::new(&config, config.request(name).unwrap())
+                .unwrap()
+                .send_blocking()
+        };
 
-        Ok(Self { client })
+        let response = send("head").unwrap();
+        assert_eq!(StatusCode::OK, response.status);
+        assert!(response.body.is_empty());
+        assert_eq!(None, response.encoded);
+        let response = send("no_content").unwrap();
+        assert_eq!(StatusCode::NO_CONTENT, response.status);
+        assert_eq!(None, response.encoded);
+        let Err(error) = send("stacked") else {
+            panic!();
+        };
+        assert!(matches!(&error, Error::Decode { encoding, .. } if (encoding == "gzip, br")));
+        for (_, handle) in [head, no_content, stacked] {
+            handle.join().unwrap();
+        }
     }
 
-    pub fn send(self) -> reqwest::Result<Response> {
-        self.client.send()
+    #[test]
+    // #[ignore]
+    fn test09() {
+        assert!(is_absolute_url("http://example.com/a"));
+        assert!(is_absolute_url("HTTPS://example.com/a"));
+        assert!(is_absolute_url("ws://localhost:3000/chat"));
+        assert!(is_absolute_url("wss://example.com/"));
+        //paths which merely start with the letters of a scheme
+        assert!(!is_absolute_url("/users"));
+        assert!(!is_absolute_url("https_proxy"));
+        assert!(!is_absolute_url("wsdl/service"));
+        assert!(!is_absolute_url("httpbin/get?next=http://example.com"));
+        assert!(!is_absolute_url("ftp://example.com/"));
     }
+    //}}}
 }
@@ -0,0 +1,208 @@
+use std::io::{self, Write};
+
+use flate2::write::{GzDecoder, ZlibDecoder};
+use reqwest::header::{HeaderMap, CONTENT_ENCODING};
+
+//the value of `Accept-Encoding` sent with `--compressed`
+pub const ACCEPT_ENCODING: &str = "gzip, deflate, br, zstd";
+
+//the content codings which are decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Gzip,
+    //zlib-wrapped DEFLATE as per RFC 9110
+    Deflate,
+    Brotli,
+    Zstd,
+}
+
+impl Encoding {
+    //`None` if `Content-Encoding` is absent or `identity`
+    //An unknown coding or two or more codings (e.g. `gzip, br`) are an error with the value of `Content-Encoding`, as the body cannot be decoded.
+    pub fn of(headers: &HeaderMap) -> Result<Option<Self>, String> {
+        let Some(v) = headers.get(CONTENT_ENCODING) else {
+            return Ok(None);
+        };
+        let s = String::from_utf8_lossy(v.as_bytes())
+            .trim()
+            .to_ascii_lowercase();
+        match (s.as_str()) {
+            "" | "identity" => Ok(None),
+            "gzip" | "x-gzip" => Ok(Some(Encoding::Gzip)),
+            "deflate" => Ok(Some(Encoding::Deflate)),
+            "br" => Ok(Some(Encoding::Brotli)),
+            "zstd" => Ok(Some(Encoding::Zstd)),
+            _ => Err(s),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match (self) {
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+            Encoding::Brotli => "br",
+            Encoding::Zstd => "zstd",
+        }
+    }
+}
+
+//decodes a body which arrives in arbitrary chunks
+//An empty body (e.g. of a `HEAD` request or a `204` response) is decoded to an empty body, though it is not a valid stream of most codings.
+pub struct Decoder {
+    inner: Inner,
+    //whether any byte has been pushed
+    is_empty: bool,
+}
+
+enum Inner {
+    Gzip(GzDecoder<Vec<u8>>),
+    Deflate(ZlibDecoder<Vec<u8>>),
+    Brotli(Box<brotli::DecompressorWriter<Vec<u8>>>),
+    Zstd(zstd::stream::write::Decoder<'static, Vec<u8>>),
+}
+
+impl Decoder {
+    pub fn new(encoding: Encoding) -> io::Result<Self> {
+        let inner = match (encoding) {
+            Encoding::Gzip => Inner::Gzip(GzDecoder::new(vec![])),
+            Encoding::Deflate => Inner::Deflate(ZlibDecoder::new(vec![])),
+            Encoding::Brotli => {
+                Inner::Brotli(Box::new(brotli::DecompressorWriter::new(vec![], 4096)))
+            }
+            Encoding::Zstd => Inner::Zstd(zstd::stream::write::Decoder::new(vec![])?),
+        };
+        Ok(Self {
+            inner,
+            is_empty: true,
+        })
+    }
+
+    //returns the bytes decoded so far from the chunks pushed
+    pub fn push(&mut self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        self.is_empty &= chunk.is_empty();
+        let output = match (&mut self.inner) {
+            Inner::Gzip(d) => {
+                d.write_all(chunk)?;
+                d.get_mut()
+            }
+            Inner::Deflate(d) => {
+                d.write_all(chunk)?;
+                d.get_mut()
+            }
+            Inner::Brotli(d) => {
+                d.write_all(chunk)?;
+                d.get_mut()
+            }
+            Inner::Zstd(d) => {
+                d.write_all(chunk)?;
+                d.get_mut()
+            }
+        };
+        Ok(std::mem::take(output))
+    }
+
+    //returns the rest of the decoded bytes
+    //A truncated body is an error for gzip and brotli, while the others return what has been decoded.
+    pub fn finish(self) -> io::Result<Vec<u8>> {
+        if (self.is_empty) {
+            return Ok(vec![]);
+        }
+        match (self.inner) {
+            Inner::Gzip(d) => d.finish(),
+            Inner::Deflate(d) => d.finish(),
+            Inner::Brotli(d) => d.into_inner().map_err(|_| {
+                io::Error::new(io::ErrorKind::UnexpectedEof, "truncated brotli stream")
+            }),
+            Inner::Zstd(mut d) => {
+                d.flush()?;
+                Ok(d.into_inner())
+            }
+        }
+    }
+}
+
+pub fn decode(encoding: Encoding, body: &[u8]) -> io::Result<Vec<u8>> {
+    let mut decoder = Decoder::new(encoding)?;
+    let mut ret = decoder.push(body)?;
+    ret.extend(decoder.finish()?);
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests_compression {
+    //{{{
+    use super::*;
+
+    use reqwest::header::HeaderValue;
+
+    fn encode(encoding: Encoding, body: &[u8]) -> Vec<u8> {
+        match (encoding) {
+            Encoding::Gzip => {
+                let mut e = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+                e.write_all(body).unwrap();
+                e.finish().unwrap()
+            }
+            Encoding::Deflate => {
+                let mut e = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+                e.write_all(body).unwrap();
+                e.finish().unwrap()
+            }
+            Encoding::Brotli => {
+                let mut e = brotli::CompressorWriter::new(vec![], 4096, 5, 22);
+                e.write_all(body).unwrap();
+                e.into_inner()
+            }
+            Encoding::Zstd => zstd::encode_all(body, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let body = "hello, world\n".repeat(1000);
+        for encoding in [
+            Encoding::Gzip,
+            Encoding::Deflate,
+            Encoding::Brotli,
+            Encoding::Zstd,
+        ] {
+            let encoded = encode(encoding, body.as_bytes());
+            assert!(encoded.len() < body.len());
+            assert_eq!(body.as_bytes(), decode(encoding, &encoded).unwrap());
+
+            //chunk by chunk
+            let mut decoder = Decoder::new(encoding).unwrap();
+            let mut decoded = vec![];
+            for chunk in encoded.chunks(7) {
+                decoded.extend(decoder.push(chunk).unwrap());
+            }
+            decoded.extend(decoder.finish().unwrap());
+            assert_eq!(body.as_bytes(), decoded, "{:?}", encoding);
+
+            //truncated
+            if (matches!(encoding, Encoding::Gzip | Encoding::Brotli)) {
+                assert!(decode(encoding, &encoded[..encoded.len() / 2]).is_err());
+            }
+        }
+
+        //an empty body
+        for encoding in [
+            Encoding::Gzip,
+            Encoding::Deflate,
+            Encoding::Brotli,
+            Encoding::Zstd,
+        ] {
+            assert_eq!(Vec::<u8>::new(), decode(encoding, &[]).unwrap());
+        }
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(Ok(None), Encoding::of(&headers));
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("GZIP"));
+        assert_eq!(Ok(Some(Encoding::Gzip)), Encoding::of(&headers));
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("identity"));
+        assert_eq!(Ok(None), Encoding::of(&headers));
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip, br"));
+        assert_eq!(Err("gzip, br".to_owned()), Encoding::of(&headers));
+    }
+    //}}}
+}
@@ -1,14 +1,19 @@
 use std::{
     collections::{HashMap, HashSet},
-    error::Error,
-    fs::File,
-    io::{BufRead, BufReader},
+    env, fs,
+    path::{Path, PathBuf},
 };
 
+use clap::ValueEnum;
 use indexmap::IndexMap;
 use regex::Regex;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
+
+use super::error::Error;
+use super::filter::Filter;
+use super::jsonc;
 
 //expands variables inside variable definition itself
 //This allows variable definition like this:
@@ -27,7 +32,7 @@ use serde_json::Value;
 fn create_local_variables(
     variables: &IndexMap<String, String>,
     global_variables: Option<&IndexMap<String, String>>,
-) -> Result<IndexMap<String, String>, Box<dyn Error>> {
+) -> Result<IndexMap<String, String>, Error> {
     let mut ret = if let Some(v) = global_variables {
         v.clone()
     } else {
@@ -47,7 +52,11 @@ fn create_local_variables(
             if let Some(v) = ret.get(variable_name) {
                 s_expanded = s_expanded.replace(placeholder, v);
             } else {
-                return Err(format!("variable `{}` is not defined", variable_name).into());
+                return Err(Error::UndefinedVariable {
+                    name: variable_name.to_owned(),
+                    request: None,
+                    path: None,
+                });
             }
         }
         ret.insert(k.clone(), s_expanded);
@@ -125,7 +134,7 @@ mod tests_create_local_variables {
 //2. performs string replace for the resultant string
 //3. serizalizes the string after the replace and returns it
 //4. it is assumed the caller would substitute (i.e. override) the returned value to `t`
-fn variable_expansion<T>(t: &T, variables: &IndexMap<String, String>) -> Result<T, Box<dyn Error>>
+fn variable_expansion<T>(t: &T, variables: &IndexMap<String, String>) -> Result<T, Error>
 where
     T: Serialize + for<'de> Deserialize<'de>,
 {
@@ -143,10 +152,14 @@ where
         if let Some(v) = variables.get(variable_name) {
             expanded_json_string = expanded_json_string.replace(placeholder, v);
         } else {
-            return Err(format!("variable `{}` is not defined", variable_name).into());
+            return Err(Error::UndefinedVariable {
+                name: variable_name.to_owned(),
+                request: None,
+                path: None,
+            });
         }
     }
-    serde_json::from_str(&expanded_json_string).map_err(|e| e.into())
+    Ok(serde_json::from_str(&expanded_json_string)?)
 }
 
 #[cfg(test)]
@@ -200,13 +213,19 @@ mod tests_variable_expansion {
 
 //recursively replaces `Value::String(s)` with `Value::Number` or `Value::Bool` if `s` starts with `number:` or `bool:`
 //This is useful for example when you want to perform a variable expansion and then cast the result to a number (e.g. `"id": "number:${id}"`).
-fn type_cast(v: &mut Value) -> Result<(), Box<dyn Error>> {
+fn type_cast(v: &mut Value) -> Result<(), Error> {
+    let error = |s: &str, e: &dyn std::fmt::Display| Error::TypeCast {
+        value: s.to_owned(),
+        message: e.to_string(),
+        request: None,
+        path: None,
+    };
     match v {
         Value::String(s) => {
             if (s.starts_with("number:")) {
-                *v = Value::Number(s.replace("number:", "").parse()?);
+                *v = Value::Number(s.replace("number:", "").parse().map_err(|e| error(s, &e))?);
             } else if (s.starts_with("bool:")) {
-                *v = Value::Bool(s.replace("bool:", "").parse()?);
+                *v = Value::Bool(s.replace("bool:", "").parse().map_err(|e| error(s, &e))?);
             }
         }
         Value::Array(l) => {
@@ -233,6 +252,8 @@ mod tests_type_cast {
 
     #[test]
     // #[ignore]
+    //`3.14` is a number to cast, not an approximation of π
+    #[allow(clippy::approx_constant)]
     fn test01() {
         let mut input = json!({
             "a": "123",
@@ -301,51 +322,366 @@ mod tests_type_cast {
     //}}}
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
+    /// URL or path of the JSON Schema of this file, which is used only by editors.
+    #[serde(default, rename = "$schema", skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    /// Any string used for comment.
     #[serde(default)]
     pub description: String,
+    /// Other config files to be included. Glob patterns are allowed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Default values for the command-line options.
     #[serde(default)]
     pub cli_options: CLIOptions,
+    /// Base URL like `http://localhost:3000`, or `unix:///var/run/docker.sock` to send the requests over a Unix domain socket.
     pub base_url: String,
+    /// Global variables. A variable defined as `{"value": "...", "secret": true}` is hidden wherever its value appears in the logs, the verbose output and `--show-config`.
     #[serde(default)]
+    #[schemars(with = "IndexMap<String, Variable>")]
     pub variables: IndexMap<String, String>,
+    /// Default HTTP request headers.
     #[serde(default)]
     pub default_headers: HashMap<String, String>,
+    /// Patterns of header names, JSON keys, query parameter names and variable names whose values are hidden in the logs, the verbose output and `--show-config`, in addition to the default ones (`Authorization`, `Cookie`, `*password*`, `*token*`, etc.). `*` matches any string, and the match is case-insensitive.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redact: Vec<String>,
+    /// Requests sent.
     pub requests: Vec<Request>,
+    //the values of the variables marked with `secret` after variable expansion
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub secrets: Vec<String>,
+    //the directory of the config file, against which relative paths are resolved
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub dir: PathBuf,
+}
+
+//used only to describe the values of `variables` in the JSON Schema
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+#[serde(untagged)]
+enum Variable {
+    String(String),
+    Secret {
+        value: String,
+        /// Hides the value in the logs, the verbose output and `--show-config`.
+        #[serde(default)]
+        secret: bool,
+    },
+}
+
+//replaces the variables defined as `{"value": "...", "secret": true}` in `value` (a config before deserialization) with their values, and returns the names of the secret ones
+//Both the global variables and the local-to-request variables are handled.
+pub fn take_secret_variables(value: &mut Value) -> HashSet<String> {
+    let mut ret = HashSet::new();
+    let mut take = |variables: Option<&mut Value>| {
+        let Some(Value::Object(m)) = variables else {
+            return;
+        };
+        for (k, v) in m.iter_mut() {
+            let Value::Object(o) = v else {
+                continue;
+            };
+            let (Some(Value::String(s)), secret) = (o.get("value"), o.get("secret")) else {
+                continue;
+            };
+            if (o.keys().any(|k| k != "value" && k != "secret")) {
+                continue;
+            }
+            match (secret) {
+                None | Some(Value::Bool(false)) => (),
+                Some(Value::Bool(true)) => {
+                    ret.insert(k.clone());
+                }
+                Some(_) => continue,
+            }
+            *v = Value::String(s.clone());
+        }
+    };
+    take(value.get_mut("variables"));
+    if let Some(Value::Array(l)) = value.get_mut("requests") {
+        for r in l {
+            take(r.get_mut("variables"));
+        }
+    }
+    ret
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Stream {
+    /// Values captured from the events. Each value is a jq expression or JSONPath evaluated for every event, and the last output other than `null` is kept and becomes a variable of the same name from the next run.
+    #[serde(default)]
+    pub capture: IndexMap<String, String>,
+    /// jq expression or JSONPath which stops reading the stream when it outputs a value other than `null` or `false` for an event.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<String>,
+    /// Maximum number of events read before the stream is closed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = 1))]
+    pub max_events: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    #[default]
+    Http,
+    WebSocket,
+}
+
+impl Protocol {
+    fn is_http(&self) -> bool {
+        *self == Protocol::Http
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct WebSocket {
+    /// Messages sent in order right after the connection is opened.
+    #[serde(default)]
+    pub messages: Vec<Message>,
+    /// jq expressions or JSONPath which the received messages must satisfy in order. Each is satisfied by the first message, after the one satisfying the previous expression, for which it outputs a value other than `null` or `false`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expect: Vec<String>,
+    /// Time (e.g. `5s`, `1m`) after which the connection is closed, counted from when it is opened. Defaults to `10s`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<String>,
+    /// Number of received messages after which the connection is closed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_messages: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase", deny_unknown_fields)]
+pub enum Message {
+    /// Text message.
+    Text(String),
+    /// Text message of the JSON string of the value.
+    Json(Value),
+    /// Binary message of the content of the file.
+    File(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct GraphQL {
+    /// GraphQL document.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    /// File (e.g. `./queries/user.graphql`) from which the GraphQL document is read, used instead of `query`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query_file: Option<String>,
+    /// GraphQL variables. Type casts (e.g. `"number:${id}"`) are applied to the values as in `body`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variables: Option<Map<String, Value>>,
+    /// Name of the operation executed when the document has two or more operations.
+    #[serde(
+        default,
+        rename = "operationName",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub operation_name: Option<String>,
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, JsonSchema, ValueEnum,
+)]
+pub enum HttpVersion {
+    /// HTTP/2 if the server agrees via ALPN on HTTPS, HTTP/1.1 otherwise
+    #[default]
+    #[serde(rename = "auto")]
+    Auto,
+    /// HTTP/1.1 only
+    #[serde(rename = "1.1")]
+    #[value(name = "1.1")]
+    Http1,
+    /// HTTP/2 with prior knowledge, i.e. without negotiation
+    #[serde(rename = "2")]
+    #[value(name = "2")]
+    Http2,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct CLIOptions {
+    /// Default value for `--show-headers` option.
     #[serde(default)]
     pub show_headers: bool,
+    /// Default value for `--timing` option.
+    #[serde(default)]
+    pub show_timing: bool,
+    /// Default value for `--disable-redirect` option.
     #[serde(default)]
     pub disable_redirect: bool,
+    /// Default value for `--max-redirects` option. Defaults to `10`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_redirects: Option<usize>,
+    /// Default value for `--same-host-redirects` option.
+    #[serde(default)]
+    pub same_host_redirects: bool,
+    /// Default value for `--http` option.
+    #[serde(default)]
+    pub http_version: HttpVersion,
+    /// Default value for `--compressed` option.
+    #[serde(default)]
+    pub compressed: bool,
+    /// Default value for `--verbose` option.
     #[serde(default)]
     pub verbose: bool,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Request {
+    /// Disables this request.
     #[serde(default)]
     pub disabled: bool,
+    /// Arbitrary human-readable name.
     pub name: String,
+    /// Any string used for comment.
     #[serde(default)]
     pub description: String,
+    /// Name of the group this request belongs to, which is used to send the requests in a group concurrently.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Local variables, which merges into and overrides the global variables. They can be marked with `secret` as the global ones.
+    #[schemars(with = "Option<IndexMap<String, Variable>>")]
     pub variables: Option<IndexMap<String, String>>,
+    /// `websocket` opens a WebSocket connection instead of sending an HTTP request.
+    #[serde(default, skip_serializing_if = "Protocol::is_http")]
+    pub protocol: Protocol,
+    /// Path part of URL (e.g. `/user/create`) appended to `base_url`.
     pub url: String,
+    /// HTTP method.
     pub method: HTTPMethod,
+    /// HTTP request headers which merges into and overrides `default_headers`.
     #[serde(default)]
     pub headers: HashMap<String, String>,
+    /// Query parameters.
     #[serde(default)]
     pub params: HashMap<String, Value>,
+    /// jq expression (e.g. `.items[].id`) or JSONPath (e.g. `$.items[*].id`) applied to the JSON response body before it is printed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    /// File to which the response body is written instead of being printed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub save_to: Option<String>,
+    /// Options for a streaming response (Server-Sent Events or NDJSON).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream: Option<Stream>,
+    /// Messages and expectations of a WebSocket request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub websocket: Option<WebSocket>,
+    /// GraphQL request, which is sent as the standard JSON body of `POST`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graphql: Option<GraphQL>,
+    /// Request body. A string is sent as it is, and a map is sent as form values or a JSON string.
+    #[schemars(with = "Option<Body>")]
     pub body: Option<Value>,
+    //the body sent as it is instead of `body`, which is set only by `ycurl replay` for a recorded body which is not UTF-8
+    #[serde(skip)]
+    pub raw_body: Option<Vec<u8>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+impl Request {
+    //returns the jq expressions and JSONPaths in the request with their paths relative to the request (e.g. `stream.until`)
+    pub fn filters(&self) -> Vec<(&str, String)> {
+        let mut ret = vec![];
+        if let Some(expr) = &self.filter {
+            ret.push((expr.as_str(), "filter".to_owned()));
+        }
+        if let Some(stream) = &self.stream {
+            for (k, expr) in &stream.capture {
+                ret.push((expr.as_str(), format!("stream.capture.{}", k)));
+            }
+            if let Some(expr) = &stream.until {
+                ret.push((expr.as_str(), "stream.until".to_owned()));
+            }
+        }
+        if let Some(websocket) = &self.websocket {
+            for (j, expr) in websocket.expect.iter().enumerate() {
+                ret.push((expr.as_str(), format!("websocket.expect[{}]", j)));
+            }
+        }
+        ret
+    }
+
+    //checks the fields of a GraphQL request, where `i` is the index of the request used in the error
+    pub fn validate_graphql(&self, i: usize) -> Result<(), Error> {
+        let Some(graphql) = &self.graphql else {
+            return Ok(());
+        };
+        let error = |message: &str, field: &str| Error::InvalidConfig {
+            message: message.to_owned(),
+            request: Some(self.name.clone()),
+            path: Some(format!("requests[{}].{}", i, field)),
+        };
+        if (self.method != HTTPMethod::Post) {
+            return Err(error("a GraphQL request must be `POST`", "method"));
+        }
+        if (self.body.is_some()) {
+            return Err(error("`body` cannot be used with `graphql`", "body"));
+        }
+        if (graphql.query.is_some() == graphql.query_file.is_some()) {
+            return Err(error(
+                "exactly one of `query` and `query_file` is required",
+                "graphql",
+            ));
+        }
+        Ok(())
+    }
+
+    //checks the fields of a WebSocket request, where `i` is the index of the request used in the error
+    pub fn validate_websocket(&self, i: usize) -> Result<(), Error> {
+        let error = |message: &str, field: &str| Error::InvalidConfig {
+            message: message.to_owned(),
+            request: Some(self.name.clone()),
+            path: Some(format!("requests[{}].{}", i, field)),
+        };
+        if (self.protocol == Protocol::Http) {
+            if (self.websocket.is_some()) {
+                return Err(error(
+                    "`websocket` requires `\"protocol\": \"websocket\"`",
+                    "websocket",
+                ));
+            }
+            return Ok(());
+        }
+        if (self.method != HTTPMethod::Get) {
+            return Err(error("a WebSocket request must be `GET`", "method"));
+        }
+        if (self.body.is_some()) {
+            return Err(error("a WebSocket request cannot have `body`", "body"));
+        }
+        if let Some(timeout) = self.websocket.as_ref().and_then(|w| w.timeout.as_ref()) {
+            if let Err(e) = humantime::parse_duration(timeout) {
+                return Err(error(
+                    &format!("invalid duration `{}`: {}", timeout, e),
+                    "websocket.timeout",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+//used only to describe `Request::body` in the JSON Schema
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+#[serde(untagged)]
+enum Body {
+    String(String),
+    Map(Map<String, Value>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum HTTPMethod {
     Get,
@@ -356,42 +692,613 @@ pub enum HTTPMethod {
     Head,
 }
 
+//returns the JSON Schema of the config file
+pub fn schema() -> Value {
+    serde_json::to_value(schemars::schema_for!(Config)).unwrap()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    //detects the format from the extension of the file, defaulting to JSON
+    pub fn from_path(path: &str) -> Self {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        match (extension.as_str()) {
+            "yaml" | "yml" => Format::Yaml,
+            "toml" => Format::Toml,
+            _ => Format::Json,
+        }
+    }
+}
+
+//an included file can only define `variables` and `requests` (and further `include`)
+#[derive(Debug, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct IncludedConfig {
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    variables: Map<String, Value>,
+    #[serde(default)]
+    requests: Vec<Value>,
+    //the file and the index in its `requests` of each request
+    #[serde(skip)]
+    request_origins: Vec<(PathBuf, usize)>,
+    #[serde(skip)]
+    files: Vec<PathBuf>,
+}
+
+//reads a config file, removing comments if it is JSON
+pub fn read_config_file(path: &Path) -> Result<String, Error> {
+    let s = fs::read_to_string(path).map_err(|e| Error::Io {
+        path: path.to_owned(),
+        source: e,
+    })?;
+    if (Format::from_path(&path.to_string_lossy()) == Format::Json) {
+        jsonc::strip(&s).map_err(|message| Error::Parse {
+            file: Some(path.to_owned()),
+            message,
+        })
+    } else {
+        Ok(s)
+    }
+}
+
+pub fn from_str<T>(s: &str, format: Format) -> Result<T, Error>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let ret = match (format) {
+        Format::Json => serde_json::from_str::<T>(s).map_err(|e| e.to_string()),
+        Format::Yaml => serde_yaml::from_str::<T>(s).map_err(|e| e.to_string()),
+        Format::Toml => toml::from_str::<T>(s).map_err(|e| e.to_string()),
+    };
+    ret.map_err(|message| Error::Parse {
+        file: None,
+        message,
+    })
+}
+
+//The entries in `overrides` override the ones in `base` and are placed after all of the ones in `base`
+//so that variables in `overrides` can refer to any variable in `base`.
+fn merge_maps(base: Map<String, Value>, overrides: Map<String, Value>) -> Map<String, Value> {
+    let mut ret = base
+        .into_iter()
+        .filter(|(k, _)| !overrides.contains_key(k))
+        .collect::<Map<String, Value>>();
+    ret.extend(overrides);
+    ret
+}
+
+//loads and merges `variables` and `requests` from the files matching `patterns`, which are relative to `dir`
+//Nested includes are resolved recursively. `stack` holds the canonical paths of the files being loaded to detect circular includes.
+fn load_included_files(
+    patterns: &[String],
+    dir: &Path,
+    stack: &mut Vec<PathBuf>,
+) -> Result<IncludedConfig, Error> {
+    let mut ret = IncludedConfig::default();
+    for pattern in patterns {
+        let pattern_path = dir.join(pattern);
+        let files = glob::glob(&pattern_path.to_string_lossy())
+            .map_err(|e| Error::InvalidConfig {
+                message: format!("invalid pattern `{}`: {}", pattern, e),
+                request: None,
+                path: Some("include".to_owned()),
+            })?
+            .collect::<Result<Vec<PathBuf>, _>>()
+            .map_err(|e| Error::Io {
+                path: e.path().to_owned(),
+                source: e.into(),
+            })?;
+        if (files.is_empty() && glob::Pattern::escape(pattern) == *pattern) {
+            return Err(Error::IncludeNotFound { file: pattern_path });
+        }
+        for file in files {
+            let canonical_path = fs::canonicalize(&file).map_err(|e| Error::Io {
+                path: file.clone(),
+                source: e,
+            })?;
+            if (stack.contains(&canonical_path)) {
+                return Err(Error::CircularInclude {
+                    chain: stack.iter().cloned().chain([canonical_path]).collect(),
+                });
+            }
+            let included = read_config_file(&file)
+                .and_then(|s| {
+                    from_str::<IncludedConfig>(&s, Format::from_path(&file.to_string_lossy()))
+                })
+                .map_err(|e| e.in_file(&file))?;
+
+            stack.push(canonical_path);
+            let nested = load_included_files(
+                &included.include,
+                file.parent().unwrap_or(Path::new(".")),
+                stack,
+            )?;
+            stack.pop();
+
+            ret.variables = merge_maps(
+                ret.variables,
+                merge_maps(nested.variables, included.variables),
+            );
+            ret.request_origins
+                .extend((0..included.requests.len()).map(|i| (file.clone(), i)));
+            ret.request_origins.extend(nested.request_origins);
+            ret.requests.extend(included.requests);
+            ret.requests.extend(nested.requests);
+            ret.files.push(file);
+            ret.files.extend(nested.files);
+        }
+    }
+    Ok(ret)
+}
+
+//merges `m` into the map-typed field `key` of `o`, where the existing entries take precedence
+fn merge_into_field(
+    o: &mut Map<String, Value>,
+    key: &str,
+    m: Map<String, Value>,
+) -> Result<(), Error> {
+    let own = match (o.get(key)) {
+        Some(Value::Object(m)) => m.clone(),
+        Some(_) => {
+            return Err(Error::InvalidConfig {
+                message: "should be a map".to_owned(),
+                request: None,
+                path: Some(key.to_owned()),
+            })
+        }
+        None => Map::new(),
+    };
+    o.insert(key.to_owned(), Value::Object(merge_maps(m, own)));
+    Ok(())
+}
+
+//user-level personal defaults, which the project config file overrides
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GlobalConfig {
+    #[serde(default)]
+    cli_options: Map<String, Value>,
+    #[serde(default)]
+    variables: Map<String, Value>,
+    #[serde(default)]
+    default_headers: Map<String, Value>,
+}
+
+pub const CONFIG_FILE_NAMES: [&str; 4] = ["ycurl.json", "ycurl.yaml", "ycurl.yml", "ycurl.toml"];
+
+//searches `dir` and its ancestors for a config file like `git` does for `.git`
+pub fn find_config_file(dir: &Path) -> Result<PathBuf, Error> {
+    for d in dir.ancestors() {
+        for name in CONFIG_FILE_NAMES {
+            let p = d.join(name);
+            if (p.is_file()) {
+                return Ok(p);
+            }
+        }
+    }
+    Err(Error::ConfigNotFound {
+        dir: dir.to_owned(),
+    })
+}
+
+//returns the path of `$XDG_CONFIG_HOME/ycurl/config.json` (defaulting to `~/.config/ycurl/config.json`) if exists
+//`config.yaml`, `config.yml` and `config.toml` are also accepted.
+pub fn find_global_config_file() -> Option<PathBuf> {
+    let dir = match (env::var("XDG_CONFIG_HOME")) {
+        Ok(d) if (!d.is_empty()) => PathBuf::from(d),
+        _ => PathBuf::from(env::var("HOME").ok()?).join(".config"),
+    }
+    .join("ycurl");
+    ["config.json", "config.yaml", "config.yml", "config.toml"]
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|p| p.is_file())
+}
+
+//returns the file where the values captured from streaming responses are kept (`$XDG_STATE_HOME/ycurl/captured.json`, or `~/.local/state/ycurl/captured.json`)
+pub fn captured_variables_file() -> Option<PathBuf> {
+    let dir = match (env::var("XDG_STATE_HOME")) {
+        Ok(d) if (!d.is_empty()) => PathBuf::from(d),
+        _ => PathBuf::from(env::var("HOME").ok()?).join(".local/state"),
+    };
+    Some(dir.join("ycurl/captured.json"))
+}
+
+//the captured values are grouped by the absolute path of the config file
+fn captured_variables_key(config_file: &str) -> String {
+    fs::canonicalize(config_file)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or(config_file.to_owned())
+}
+
+fn read_captured_variables(p: &Path) -> Result<Map<String, Value>, Error> {
+    if (!p.is_file()) {
+        return Ok(Map::new());
+    }
+    let s = fs::read_to_string(p).map_err(|e| Error::Io {
+        path: p.to_owned(),
+        source: e,
+    })?;
+    serde_json::from_str(&s).map_err(|e| Error::from(e).in_file(p))
+}
+
+//returns the variables captured from the streaming responses of the requests in `config_file`
+pub fn load_captured_variables(config_file: &str) -> Result<Map<String, Value>, Error> {
+    let Some(p) = captured_variables_file() else {
+        return Ok(Map::new());
+    };
+    match (read_captured_variables(&p)?.remove(&captured_variables_key(config_file))) {
+        Some(Value::Object(m)) => Ok(m),
+        _ => Ok(Map::new()),
+    }
+}
+
+//stores the values captured from a streaming response of a request in `config_file` so that they are used as variables from the next run
+//A value other than a string is stored as JSON text.
+pub fn save_captured_variables(
+    config_file: &str,
+    captured: &IndexMap<String, Value>,
+) -> Result<(), Error> {
+    let Some(p) = captured_variables_file() else {
+        return Ok(());
+    };
+    let mut all = read_captured_variables(&p)?;
+    let key = captured_variables_key(config_file);
+    let mut m = match (all.remove(&key)) {
+        Some(Value::Object(m)) => m,
+        _ => Map::new(),
+    };
+    for (k, v) in captured {
+        let v = match (v) {
+            Value::String(s) => s.clone(),
+            v => v.to_string(),
+        };
+        m.insert(k.clone(), Value::String(v));
+    }
+    all.insert(key, Value::Object(m));
+
+    let io_error = |path: &Path, e| Error::Io {
+        path: path.to_owned(),
+        source: e,
+    };
+    let dir = p.parent().unwrap();
+    fs::create_dir_all(dir).map_err(|e| io_error(dir, e))?;
+    //written via a temporary file so that an interrupted write does not break the file
+    let tmp = p.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_string_pretty(&all)? + "\n").map_err(|e| io_error(&tmp, e))?;
+    fs::rename(&tmp, &p).map_err(|e| io_error(&p, e))
+}
+
+//puts the captured variables before the other variables so that these can refer to the captured ones
+//A variable defined in the config takes precedence over the captured one of the same name. Returns the names of the captured variables merged.
+fn merge_captured_variables(
+    o: &mut Map<String, Value>,
+    captured: Map<String, Value>,
+) -> Result<Vec<String>, Error> {
+    let own = match (o.remove("variables")) {
+        Some(Value::Object(m)) => m,
+        Some(_) => {
+            return Err(Error::InvalidConfig {
+                message: "should be a map".to_owned(),
+                request: None,
+                path: Some("variables".to_owned()),
+            })
+        }
+        None => Map::new(),
+    };
+    let mut ret = captured;
+    ret.retain(|k, _| !own.contains_key(k));
+    let merged = ret.keys().cloned().collect();
+    ret.extend(own);
+    o.insert("variables".to_owned(), Value::Object(ret));
+    Ok(merged)
+}
+
+//a config before variable expansion, with the information about where each part comes from
+#[derive(Debug, Clone)]
+pub struct RawConfig {
+    pub value: Value,
+    //the config file, the included files and the global config file
+    pub files: Vec<PathBuf>,
+    //the file and the index in its `requests` of each request
+    pub request_origins: Vec<(PathBuf, usize)>,
+    //the names of the variables defined in the global config file
+    pub global_variables: Vec<String>,
+    //the names of the variables captured from streaming responses
+    pub captured_variables: Vec<String>,
+}
+
+//loads the config file, resolving `include` and merging the global config file and `captured` (the values returned by `load_captured_variables()`)
+pub fn load_raw_config(
+    config_file: &str,
+    global_config_file: Option<&Path>,
+    captured: Map<String, Value>,
+) -> Result<RawConfig, Error> {
+    let path = Path::new(config_file);
+    let mut value = from_str::<Value>(&read_config_file(path)?, Format::from_path(config_file))
+        .map_err(|e| e.in_file(path))?;
+    let o = value.as_object_mut().ok_or_else(|| Error::Parse {
+        file: Some(path.to_owned()),
+        message: "config should be a map".to_owned(),
+    })?;
+
+    let mut files = vec![path.to_owned()];
+    let mut request_origins = match (o.get("requests")) {
+        Some(Value::Array(l)) => (0..l.len()).map(|i| (path.to_owned(), i)).collect(),
+        _ => vec![],
+    };
+
+    if let Some(v) = o.get("include") {
+        let patterns = serde_json::from_value::<Vec<String>>(v.clone())
+            .map_err(|e| Error::from(e).in_file(path))?;
+        let canonical_path = fs::canonicalize(path).map_err(|e| Error::Io {
+            path: path.to_owned(),
+            source: e,
+        })?;
+        let included = load_included_files(
+            &patterns,
+            path.parent().unwrap_or(Path::new(".")),
+            &mut vec![canonical_path],
+        )?;
+        merge_into_field(o, "variables", included.variables)?;
+        match (o.get_mut("requests")) {
+            Some(Value::Array(l)) => l.extend(included.requests),
+            Some(_) => {
+                return Err(Error::InvalidConfig {
+                    message: "should be an array".to_owned(),
+                    request: None,
+                    path: Some("requests".to_owned()),
+                })
+            }
+            None => {
+                o.insert("requests".to_owned(), Value::Array(included.requests));
+            }
+        }
+        files.extend(included.files);
+        request_origins.extend(included.request_origins);
+    }
+
+    let mut global_variables = vec![];
+    if let Some(p) = global_config_file {
+        let global = read_config_file(p)
+            .and_then(|s| from_str::<GlobalConfig>(&s, Format::from_path(&p.to_string_lossy())))
+            .map_err(|e| e.in_file(p))?;
+        global_variables = global.variables.keys().cloned().collect();
+        merge_into_field(o, "cli_options", global.cli_options)?;
+        merge_into_field(o, "variables", global.variables)?;
+        merge_into_field(o, "default_headers", global.default_headers)?;
+        files.push(p.to_owned());
+    }
+
+    let captured_variables = if (captured.is_empty()) {
+        vec![]
+    } else {
+        merge_captured_variables(o, captured)?
+    };
+
+    Ok(RawConfig {
+        value,
+        files,
+        request_origins,
+        global_variables,
+        captured_variables,
+    })
+}
+
+//returns the path of the first string in `v` satisfying `f`
+fn find_string(v: &Value, path: String, f: &dyn Fn(&str) -> bool) -> Option<String> {
+    match v {
+        Value::String(s) if (f(s)) => Some(path),
+        Value::Array(l) => l
+            .iter()
+            .enumerate()
+            .find_map(|(i, e)| find_string(e, format!("{}[{}]", path, i), f)),
+        Value::Object(o) => o
+            .iter()
+            .find_map(|(k, e)| find_string(e, format!("{}.{}", path, k), f)),
+        _ => None,
+    }
+}
+
+//fills in the request name and the path of `e`
+//The path is searched for in `t`, which is located at `path` in the config.
+fn add_context<T: Serialize>(e: Error, request: Option<&str>, path: &str, t: &T) -> Error {
+    let v = serde_json::to_value(t).unwrap();
+    let request = request.map(|s| s.to_owned());
+    match (e) {
+        Error::UndefinedVariable { name, .. } => {
+            let placeholder = format!("${{{}}}", name);
+            Error::UndefinedVariable {
+                path: find_string(&v, path.to_owned(), &|s| s.contains(&placeholder)),
+                name,
+                request,
+            }
+        }
+        Error::TypeCast { value, message, .. } => Error::TypeCast {
+            path: find_string(&v, path.to_owned(), &|s| s == value),
+            value,
+            message,
+            request,
+        },
+        e => e,
+    }
+}
+
 impl Config {
-    pub fn new(config_file: &str) -> Result<Self, Box<dyn Error>> {
-        let json_string: String = {
-            let file = File::open(config_file)?;
-            let comment_regex = Regex::new(r#"^\s*#.*"#)?;
-            BufReader::new(file)
-                .lines()
-                .filter(|l| !comment_regex.is_match(l.as_ref().unwrap()))
-                .map(|l| l.unwrap())
-                .collect::<Vec<String>>()
-                .join("\n")
+    pub fn new(config_file: &str, global_config_file: Option<&Path>) -> Result<Self, Error> {
+        Self::new_with_captured_variables(config_file, global_config_file, Map::new())
+    }
+
+    //`captured` is the values returned by `load_captured_variables()`, which are used as global variables unless the config defines the same names
+    pub fn new_with_captured_variables(
+        config_file: &str,
+        global_config_file: Option<&Path>,
+        captured: Map<String, Value>,
+    ) -> Result<Self, Error> {
+        let path = Path::new(config_file);
+        let format = Format::from_path(config_file);
+        let s = read_config_file(path)?;
+        let value = from_str::<Value>(&s, format).map_err(|e| e.in_file(path))?;
+        let has_secrets = !take_secret_variables(&mut value.clone()).is_empty();
+        if (value.get("include").is_none()
+            && global_config_file.is_none()
+            && !has_secrets
+            && captured.is_empty())
+        {
+            //deserializes directly from the string so that errors have line numbers
+            let ret = Config::new_with_string(&s, format).map_err(|e| e.in_file(path))?;
+            return Ok(ret.resolve_paths(path.parent().unwrap_or(Path::new("."))));
+        }
+        let mut raw = load_raw_config(config_file, global_config_file, captured)?;
+        let secret_variables = take_secret_variables(&mut raw.value);
+        let ret = Config::initialize(
+            serde_json::from_value::<Self>(raw.value).map_err(|e| Error::from(e).in_file(path))?,
+            &secret_variables,
+        )?;
+        Ok(ret.resolve_paths(path.parent().unwrap_or(Path::new("."))))
+    }
+
+    //resolves the relative paths in the requests (`save_to`, `graphql.query_file` and the files of `websocket.messages`) against `dir`, so that they do not depend on the current directory
+    //The paths in the included files are also resolved against the directory of the main config file.
+    fn resolve_paths(mut self, dir: &Path) -> Self {
+        self.dir = dir.to_owned();
+        let resolve = |p: &mut String| {
+            if (Path::new(p.as_str()).is_relative()) {
+                *p = dir.join(&*p).to_string_lossy().into_owned();
+            }
         };
-        Config::new_with_json_string(&json_string)
+        for request in &mut self.requests {
+            if let Some(p) = &mut request.save_to {
+                resolve(p);
+            }
+            if let Some(p) = request.graphql.as_mut().and_then(|g| g.query_file.as_mut()) {
+                resolve(p);
+            }
+            for message in request.websocket.iter_mut().flat_map(|w| &mut w.messages) {
+                if let Message::File(p) = message {
+                    resolve(p);
+                }
+            }
+        }
+        self
     }
 
-    fn new_with_json_string(json_string: &str) -> Result<Self, Box<dyn Error>> {
-        let mut ret = serde_json::from_str::<Self>(json_string)?;
+    //expands the variables of `request` in `path` given by `--output-file`
+    //Unlike `save_to`, a relative path is left relative to the current directory, as it is given on the command line.
+    pub fn output_file(&self, request: &Request, path: &str) -> Result<PathBuf, Error> {
+        let variables = match (&request.variables) {
+            Some(m) => create_local_variables(m, Some(&self.variables))?,
+            None => self.variables.clone(),
+        };
+        let expanded = variable_expansion(&path.to_owned(), &variables).map_err(|e| match (e) {
+            Error::UndefinedVariable { name, .. } => Error::UndefinedVariable {
+                name,
+                request: Some(request.name.clone()),
+                path: Some("--output-file".to_owned()),
+            },
+            e => e,
+        })?;
+        Ok(PathBuf::from(expanded))
+    }
 
-        ret.variables = create_local_variables(&ret.variables, None)?;
+    //returns the request of the given index or name
+    pub fn request(&self, index_or_name: &str) -> Result<&Request, Error> {
+        let ret = match (index_or_name.parse::<usize>()) {
+            Ok(i) => self.requests.get(i),
+            Err(_) => self.requests.iter().find(|r| r.name == index_or_name),
+        };
+        ret.ok_or_else(|| Error::RequestNotFound(index_or_name.to_owned()))
+    }
+
+    //returns the requests in the given group
+    pub fn group(&self, name: &str) -> Result<Vec<&Request>, Error> {
+        let ret = self
+            .requests
+            .iter()
+            .filter(|r| r.group.as_deref() == Some(name))
+            .collect::<Vec<_>>();
+        if (ret.is_empty()) {
+            return Err(Error::GroupNotFound(name.to_owned()));
+        }
+        Ok(ret)
+    }
+
+    fn new_with_string(s: &str, format: Format) -> Result<Self, Error> {
+        Config::initialize(from_str::<Self>(s, format)?, &HashSet::new())
+    }
+
+    //performs variable expansion, type cast and validation
+    //`secret_variables` are the names of the variables whose values are collected in `secrets`.
+    fn initialize(mut ret: Self, secret_variables: &HashSet<String>) -> Result<Self, Error> {
+        ret.variables = create_local_variables(&ret.variables, None)
+            .map_err(|e| add_context(e, None, "variables", &ret.variables))?;
+        ret.secrets = ret
+            .variables
+            .iter()
+            .filter(|(k, _)| secret_variables.contains(*k))
+            .map(|(_, v)| v.clone())
+            .collect();
 
         //performs variable expansion
-        ret.default_headers = variable_expansion(&ret.default_headers, &ret.variables)?;
+        ret.default_headers = variable_expansion(&ret.default_headers, &ret.variables)
+            .map_err(|e| add_context(e, None, "default_headers", &ret.default_headers))?;
         for i in 0..ret.requests.len() {
+            let request = &ret.requests[i];
             //merges the global `variables` and local-to-request `variables`
-            if let Some(m) = &ret.requests[i].variables {
-                let variables = create_local_variables(m, Some(&ret.variables))?;
-                ret.requests[i] = variable_expansion(&ret.requests[i], &variables)?;
+            let variables = if let Some(m) = &request.variables {
+                create_local_variables(m, Some(&ret.variables)).map_err(|e| {
+                    add_context(
+                        e,
+                        Some(&request.name),
+                        &format!("requests[{}].variables", i),
+                        m,
+                    )
+                })?
             } else {
-                ret.requests[i] = variable_expansion(&ret.requests[i], &ret.variables)?;
+                ret.variables.clone()
+            };
+            for k in request.variables.iter().flat_map(|m| m.keys()) {
+                if (secret_variables.contains(k)) {
+                    ret.secrets.push(variables[k].clone());
+                }
             }
+            ret.requests[i] = variable_expansion(request, &variables).map_err(|e| {
+                add_context(e, Some(&request.name), &format!("requests[{}]", i), request)
+            })?;
         }
 
         for i in 0..ret.requests.len() {
-            if let Some(Value::Object(ref mut v)) = ret.requests[i].body {
-                for (_, v) in v.iter_mut() {
-                    type_cast(v)?;
+            let request = &mut ret.requests[i];
+            let mut maps = vec![];
+            if let Some(Value::Object(ref mut v)) = request.body {
+                maps.push(("body", v));
+            }
+            if let Some(Some(v)) = request.graphql.as_mut().map(|g| g.variables.as_mut()) {
+                maps.push(("graphql.variables", v));
+            }
+            for (field, m) in maps {
+                for (k, v) in m.iter_mut() {
+                    let original = v.clone();
+                    type_cast(v).map_err(|e| {
+                        add_context(
+                            e,
+                            Some(&request.name),
+                            &format!("requests[{}].{}.{}", i, field, k),
+                            &original,
+                        )
+                    })?;
                 }
             }
         }
@@ -401,26 +1308,75 @@ impl Config {
         Ok(ret)
     }
 
-    fn validate(&self) -> Result<(), Box<dyn Error>> {
+    fn validate(&self) -> Result<(), Error> {
+        for (i, pattern) in self.redact.iter().enumerate() {
+            if let Err(e) = glob::Pattern::new(pattern) {
+                return Err(Error::InvalidConfig {
+                    message: format!("invalid pattern `{}`: {}", pattern, e),
+                    request: None,
+                    path: Some(format!("redact[{}]", i)),
+                });
+            }
+        }
         let mut s = HashSet::new();
         for i in 0..self.requests.len() {
             if (s.contains(&self.requests[i].name)) {
-                return Err(format!(
-                    "two or more entries have the same name: {}",
-                    self.requests[i].name
-                )
-                .into());
+                return Err(Error::InvalidConfig {
+                    message: format!(
+                        "two or more entries have the same name: {}",
+                        self.requests[i].name
+                    ),
+                    request: Some(self.requests[i].name.clone()),
+                    path: Some(format!("requests[{}].name", i)),
+                });
             }
             s.insert(&self.requests[i].name);
 
             match self.requests[i].body {
                 None | Some(Value::String(_)) | Some(Value::Object(_)) => (),
                 _ => {
-                    return Err(
-                        format!("unsupported `body` type: {:?}", self.requests[i].body).into(),
-                    )
+                    return Err(Error::InvalidConfig {
+                        message: format!("unsupported `body` type: {:?}", self.requests[i].body),
+                        request: Some(self.requests[i].name.clone()),
+                        path: Some(format!("requests[{}].body", i)),
+                    })
                 }
             }
+
+            self.requests[i].validate_websocket(i)?;
+            self.requests[i].validate_graphql(i)?;
+            if let Some(Stream {
+                max_events: Some(0),
+                ..
+            }) = &self.requests[i].stream
+            {
+                return Err(Error::InvalidConfig {
+                    message: "should be greater than 0".to_owned(),
+                    request: Some(self.requests[i].name.clone()),
+                    path: Some(format!("requests[{}].stream.max_events", i)),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    //compiles the expressions in `request` (a request in `self`)
+    //This is done only for the requests to be sent so that a broken expression in another request does not stop them. `ycurl lint` checks all of them.
+    pub fn validate_filters(&self, request: &Request) -> Result<(), Error> {
+        let i = self
+            .requests
+            .iter()
+            .position(|r| r.name == request.name)
+            .unwrap_or_default();
+        for (expr, path) in request.filters() {
+            if let Err(Error::Filter { expr, message, .. }) = Filter::new(expr) {
+                return Err(Error::Filter {
+                    expr,
+                    message,
+                    request: Some(request.name.clone()),
+                    path: Some(format!("requests[{}].{}", i, path)),
+                });
+            }
         }
         Ok(())
     }
@@ -492,7 +1448,11 @@ mod tests_config {
         let expected = json!({
             "cli_options": {
                 "show_headers": true,
+                "show_timing": false,
                 "disable_redirect": false,
+                "same_host_redirects": false,
+                "http_version": "auto",
+                "compressed": false,
                 "verbose": false,
             },
             "description": "desc",
@@ -541,7 +1501,7 @@ mod tests_config {
             ]
         });
 
-        let config = Config::new_with_json_string(input);
+        let config = Config::new_with_string(input, Format::Json);
         println!("{:?}", config);
         assert!(config.is_ok());
         let config = config.unwrap();
@@ -549,5 +1509,592 @@ mod tests_config {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    // #[ignore]
+    fn test02() {
+        let input = r#"
+cli_options:
+  show_headers: true
+base_url: http://example.com
+variables:
+  id: "123"
+  name: Mike
+# a comment
+requests:
+  - name: req1
+    variables:
+      flag: "true"
+    url: /v1/user/${id}
+    method: POST
+    body:
+      text: |
+        multi-line
+        body of ${name}
+      id: number:${id}
+      flag: bool:${flag}
+"#;
+
+        let expected = json!({
+            "cli_options": {
+                "show_headers": true,
+                "show_timing": false,
+                "disable_redirect": false,
+                "same_host_redirects": false,
+                "http_version": "auto",
+                "compressed": false,
+                "verbose": false,
+            },
+            "description": "",
+            "base_url": "http://example.com",
+            "variables": {
+                "id": "123",
+                "name": "Mike"
+            },
+            "default_headers": {},
+            "requests": [
+                {
+                    "disabled": false,
+                    "name": "req1",
+                    "description": "",
+                    "variables": {
+                        "flag": "true"
+                    },
+                    "url": "/v1/user/123",
+                    "method": "POST",
+                    "headers": {},
+                    "params": {},
+                    "body": {
+                        "text": "multi-line\nbody of Mike\n",
+                        "id": 123,
+                        "flag": true
+                    }
+                }
+            ]
+        });
+
+        let config = Config::new_with_string(input, Format::Yaml);
+        println!("{:?}", config);
+        assert!(config.is_ok());
+        let actual = serde_json::to_value(config.unwrap()).unwrap();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    // #[ignore]
+    fn test03() {
+        let input = r#"
+base_url = "http://example.com"
+
+[variables]
+id = "123"
+name = "Mike"
+
+[[requests]]
+name = "req1"
+url = "/v1/user/${id}"
+method = "POST"
+
+[requests.variables]
+flag = "true"
+
+[requests.body]
+text = """
+multi-line
+body of ${name}"""
+id = "number:${id}"
+flag = "bool:${flag}"
+"#;
+
+        let expected = json!({
+            "cli_options": {
+                "show_headers": false,
+                "show_timing": false,
+                "disable_redirect": false,
+                "same_host_redirects": false,
+                "http_version": "auto",
+                "compressed": false,
+                "verbose": false,
+            },
+            "description": "",
+            "base_url": "http://example.com",
+            "variables": {
+                "id": "123",
+                "name": "Mike"
+            },
+            "default_headers": {},
+            "requests": [
+                {
+                    "disabled": false,
+                    "name": "req1",
+                    "description": "",
+                    "variables": {
+                        "flag": "true"
+                    },
+                    "url": "/v1/user/123",
+                    "method": "POST",
+                    "headers": {},
+                    "params": {},
+                    "body": {
+                        "text": "multi-line\nbody of Mike",
+                        "id": 123,
+                        "flag": true
+                    }
+                }
+            ]
+        });
+
+        let config = Config::new_with_string(input, Format::Toml);
+        println!("{:?}", config);
+        assert!(config.is_ok());
+        let actual = serde_json::to_value(config.unwrap()).unwrap();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    // #[ignore]
+    fn test04() {
+        assert_eq!(Format::Json, Format::from_path("./ycurl.json"));
+        assert_eq!(Format::Json, Format::from_path("./ycurl"));
+        assert_eq!(Format::Yaml, Format::from_path("./ycurl.yaml"));
+        assert_eq!(Format::Yaml, Format::from_path("/a/b/ycurl.YML"));
+        assert_eq!(Format::Toml, Format::from_path("ycurl.toml"));
+    }
+
+    #[test]
+    // #[ignore]
+    fn test05() {
+        let dir = std::env::temp_dir().join(format!("ycurl_tests_config_{}", std::process::id()));
+        fs::create_dir_all(dir.join("requests/nested")).unwrap();
+        fs::write(
+            dir.join("ycurl.json"),
+            r#"{
+                "base_url": "http://example.com",
+                "include": ["./requests/*.json"],
+                "variables": {
+                    "name": "${prefix}_Mike"
+                },
+                "requests": [
+                    {"name": "root", "url": "/${name}", "method": "GET"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("requests/a.json"),
+            r#"{
+                "include": ["nested/c.yaml"],
+                "variables": {"prefix": "a", "id": "1"},
+                "requests": [
+                    {"name": "a", "url": "/${id}", "method": "GET"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("requests/b.json"),
+            r#"{
+                "requests": [
+                    {"name": "b", "url": "/${prefix}", "method": "GET"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("requests/nested/c.yaml"),
+            "variables:\n  id: '2'\nrequests:\n  - {name: c, url: '/${id}', method: GET}\n",
+        )
+        .unwrap();
+
+        let config = Config::new(&dir.join("ycurl.json").to_string_lossy(), None);
+        println!("{:?}", config);
+        assert!(config.is_ok());
+        let config = config.unwrap();
+        assert_eq!(vec!["./requests/*.json"], config.include);
+        assert_eq!("a_Mike", config.variables["name"]);
+        assert_eq!("1", config.variables["id"]);
+        assert_eq!(
+            vec!["root", "a", "c", "b"],
+            config.requests.iter().map(|r| &r.name).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["/a_Mike", "/1", "/1", "/a"],
+            config.requests.iter().map(|r| &r.url).collect::<Vec<_>>()
+        );
+
+        //circular include
+        fs::write(
+            dir.join("requests/nested/c.yaml"),
+            "include: ['../a.json']\n",
+        )
+        .unwrap();
+        let config = Config::new(&dir.join("ycurl.json").to_string_lossy(), None);
+        println!("{:?}", config);
+        assert!(config
+            .map_err(|e| e.to_string())
+            .unwrap_err()
+            .starts_with("circular include: "));
+
+        //unknown field in an included file
+        fs::write(
+            dir.join("requests/nested/c.yaml"),
+            "base_url: http://example.com\n",
+        )
+        .unwrap();
+        let config = Config::new(&dir.join("ycurl.json").to_string_lossy(), None);
+        println!("{:?}", config);
+        assert!(config.is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    // #[ignore]
+    fn test06() {
+        let dir =
+            std::env::temp_dir().join(format!("ycurl_tests_config_06_{}", std::process::id()));
+        fs::create_dir_all(dir.join("a/b")).unwrap();
+        fs::write(
+            dir.join("ycurl.json"),
+            r#"{
+                "cli_options": {"verbose": false},
+                "base_url": "http://example.com",
+                "variables": {"token": "${user}_token"},
+                "default_headers": {"Authorization": "Bearer ${token}"},
+                "requests": [
+                    {"name": "a", "url": "/", "method": "GET"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("global.yaml"),
+            "cli_options:\n  show_headers: true\n  verbose: true\nvariables:\n  user: mike\n  token: xxx\ndefault_headers:\n  Authorization: yyy\n  Accept: application/json\n",
+        )
+        .unwrap();
+
+        //discovery
+        let p = find_config_file(&dir.join("a/b"));
+        assert!(p.is_ok());
+        assert_eq!(dir.join("ycurl.json"), p.unwrap());
+        assert!(find_config_file(Path::new("/")).is_err());
+
+        //global config
+        let config = Config::new(
+            &dir.join("ycurl.json").to_string_lossy(),
+            Some(&dir.join("global.yaml")),
+        );
+        println!("{:?}", config);
+        assert!(config.is_ok());
+        let config = config.unwrap();
+        assert!(config.cli_options.show_headers);
+        assert!(!config.cli_options.verbose);
+        assert_eq!("mike", config.variables["user"]);
+        assert_eq!("mike_token", config.variables["token"]);
+        assert_eq!("Bearer mike_token", config.default_headers["Authorization"]);
+        assert_eq!("application/json", config.default_headers["Accept"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    // #[ignore]
+    fn test07() {
+        //`ycurl.schema.json` is kept in sync with the definitions (regenerate it via `ycurl schema > ycurl.schema.json`)
+        let expected = serde_json::from_str::<Value>(include_str!("../ycurl.schema.json")).unwrap();
+        assert_eq!(expected, schema());
+
+        let input = r#"
+            {
+                "$schema": "./ycurl.schema.json",
+                "base_url": "http://example.com",
+                "requests": []
+            }
+        "#;
+        let config = Config::new_with_string(input, Format::Json).unwrap();
+        assert_eq!(Some("./ycurl.schema.json"), config.schema.as_deref());
+    }
+
+    #[test]
+    // #[ignore]
+    fn test08() {
+        //errors carry the request name and the path
+        let input = r#"
+            {
+                "base_url": "http://example.com",
+                "variables": {"id": "1"},
+                "requests": [
+                    {"name": "a", "url": "/${id}", "method": "GET"},
+                    {"name": "b", "url": "/", "method": "GET", "params": {"q": "${x}"}}
+                ]
+            }
+        "#;
+        let e = Config::new_with_string(input, Format::Json).unwrap_err();
+        assert_eq!(
+            "requests[1].params.q (b): variable `x` is not defined",
+            e.to_string()
+        );
+        assert_eq!(5, e.exit_code());
+
+        let input = r#"
+            {
+                "base_url": "http://example.com",
+                "requests": [
+                    {"name": "a", "url": "/", "method": "POST", "body": {"a": {"b": ["bool:x"]}}}
+                ]
+            }
+        "#;
+        let e = Config::new_with_string(input, Format::Json).unwrap_err();
+        match (&e) {
+            Error::TypeCast { request, path, .. } => {
+                assert_eq!(Some("a"), request.as_deref());
+                assert_eq!(Some("requests[0].body.a.b[0]"), path.as_deref());
+            }
+            _ => panic!("{:?}", e),
+        }
+        assert_eq!(6, e.exit_code());
+
+        let input = r#"
+            {
+                "base_url": "http://example.com",
+                "requests": [
+                    {"name": "a", "url": "/", "method": "GET", "filter": ".items[].id"},
+                    {"name": "b", "url": "/", "method": "GET", "filter": ".items["}
+                ]
+            }
+        "#;
+        let config = Config::new_with_string(input, Format::Json).unwrap();
+        let e = config.validate_filters(&config.requests[1]).unwrap_err();
+        assert_eq!(
+            "requests[1].filter (b): filter `.items[`: expected closing bracket at the end",
+            e.to_string()
+        );
+        assert_eq!(6, e.exit_code());
+
+        let input = r#"
+            {
+                "base_url": "http://example.com",
+                "requests": [
+                    {"name": "a", "protocol": "websocket", "url": "/", "method": "GET", "websocket": {"timeout": "1x"}}
+                ]
+            }
+        "#;
+        let e = Config::new_with_string(input, Format::Json).unwrap_err();
+        assert!(e
+            .to_string()
+            .starts_with("requests[0].websocket.timeout (a): invalid duration `1x`"));
+        let input = r#"
+            {
+                "base_url": "http://example.com",
+                "requests": [
+                    {"name": "a", "url": "/", "method": "GET", "websocket": {"messages": [{"text": "hi"}]}}
+                ]
+            }
+        "#;
+        let e = Config::new_with_string(input, Format::Json).unwrap_err();
+        assert_eq!(
+            r#"requests[0].websocket (a): `websocket` requires `"protocol": "websocket"`"#,
+            e.to_string()
+        );
+
+        let input = r#"
+            {
+                "base_url": "http://example.com",
+                "requests": [
+                    {"name": "a", "url": "/", "method": "POST", "graphql": {"query": "{ a }", "variables": {"n": "number:x"}}},
+                    {"name": "b", "url": "/", "method": "POST", "graphql": {"query": "{ a }", "query_file": "a.graphql"}}
+                ]
+            }
+        "#;
+        let e = Config::new_with_string(input, Format::Json).unwrap_err();
+        assert!(e
+            .to_string()
+            .starts_with("requests[0].graphql.variables.n (a): "));
+        let input = input.replace("number:x", "number:1");
+        let e = Config::new_with_string(&input, Format::Json).unwrap_err();
+        assert_eq!(
+            "requests[1].graphql (b): exactly one of `query` and `query_file` is required",
+            e.to_string()
+        );
+    }
+
+    #[test]
+    // #[ignore]
+    fn test09() {
+        //secret variables
+        let dir =
+            std::env::temp_dir().join(format!("ycurl_tests_config_09_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("ycurl.json"),
+            r#"{
+                "base_url": "http://example.com",
+                "variables": {
+                    "user": "mike",
+                    "key": {"value": "k_${user}", "secret": true},
+                    "plain": {"value": "p"}
+                },
+                "redact": ["x-api-*"],
+                "requests": [
+                    {"name": "a", "url": "/${plain}", "method": "GET", "headers": {"X-Api-Key": "${key}"}},
+                    {"name": "b", "url": "/", "method": "GET", "variables": {"pin": {"value": "1234", "secret": true}}}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let config = Config::new(&dir.join("ycurl.json").to_string_lossy(), None).unwrap();
+        assert_eq!("k_mike", config.variables["key"]);
+        assert_eq!("/p", config.requests[0].url);
+        assert_eq!("k_mike", config.requests[0].headers["X-Api-Key"]);
+        assert_eq!(vec!["k_mike", "1234"], config.secrets);
+        assert_eq!(vec!["x-api-*"], config.redact);
+        fs::remove_dir_all(dir).unwrap();
+
+        let input = r#"
+            {
+                "base_url": "http://example.com",
+                "redact": ["x-*", "[x"],
+                "requests": []
+            }
+        "#;
+        let e = Config::new_with_string(input, Format::Json).unwrap_err();
+        assert!(e.to_string().starts_with("redact[1]: invalid pattern `[x`"));
+    }
+
+    #[test]
+    // #[ignore]
+    fn test10() {
+        //captured variables come first, but do not override the definitions of the same names
+        let mut o = serde_json::from_str::<Map<String, Value>>(
+            r#"{
+                "variables": {
+                    "user": "mike",
+                    "token": {"value": "old", "secret": true},
+                    "header": "Bearer ${token}"
+                }
+            }"#,
+        )
+        .unwrap();
+        let captured = serde_json::from_str(r#"{"token": "new", "cursor": "10"}"#).unwrap();
+        assert_eq!(
+            vec!["cursor"],
+            merge_captured_variables(&mut o, captured).unwrap()
+        );
+        assert_eq!(
+            serde_json::from_str::<Value>(
+                r#"{
+                    "cursor": "10",
+                    "user": "mike",
+                    "token": {"value": "old", "secret": true},
+                    "header": "Bearer ${token}"
+                }"#
+            )
+            .unwrap(),
+            o["variables"]
+        );
+        assert_eq!(
+            vec!["cursor", "user", "token", "header"],
+            o["variables"]
+                .as_object()
+                .unwrap()
+                .keys()
+                .collect::<Vec<_>>()
+        );
+
+        let input = r#"
+            {
+                "base_url": "http://example.com",
+                "requests": [
+                    {"name": "a", "url": "/", "method": "GET", "stream": {"max_events": 0}}
+                ]
+            }
+        "#;
+        let e = Config::new_with_string(input, Format::Json).unwrap_err();
+        assert_eq!(
+            "requests[0].stream.max_events (a): should be greater than 0",
+            e.to_string()
+        );
+    }
+
+    #[test]
+    // #[ignore]
+    fn test11() {
+        //a broken expression is an error only when its request is sent
+        let input = r#"
+            {
+                "base_url": "http://example.com",
+                "requests": [
+                    {"name": "a", "url": "/", "method": "GET", "filter": ".id"},
+                    {"name": "b", "url": "/", "method": "GET", "stream": {"capture": {"x": ".["}}}
+                ]
+            }
+        "#;
+        let config = Config::new_with_string(input, Format::Json).unwrap();
+        assert!(config.validate_filters(&config.requests[0]).is_ok());
+        let e = config.validate_filters(&config.requests[1]).unwrap_err();
+        assert!(matches!(
+            e,
+            Error::Filter {
+                request: Some(ref name),
+                path: Some(ref path),
+                ..
+            } if (name == "b" && path == "requests[1].stream.capture.x")
+        ));
+    }
+
+    #[test]
+    // #[ignore]
+    fn test12() {
+        //relative paths are resolved against the directory of the config file
+        let dir = env::temp_dir().join(format!("ycurl_tests_config_12_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config_file = dir.join("ycurl.json");
+        fs::write(
+            &config_file,
+            r#"{
+                "base_url": "http://localhost",
+                "variables": {"out": "out"},
+                "requests": [
+                    {"name": "a", "url": "/", "method": "GET", "variables": {"id": "1"}, "save_to": "${out}/a.json"},
+                    {"name": "b", "url": "/", "method": "POST", "graphql": {"query_file": "q.graphql"}},
+                    {"name": "c", "protocol": "websocket", "url": "/", "method": "GET", "websocket": {"messages": [{"file": "/tmp/m.bin"}, {"file": "m.bin"}]}}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let config = Config::new(&config_file.to_string_lossy(), None).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            Some(dir.join("out/a.json").to_string_lossy().into_owned()),
+            config.requests[0].save_to
+        );
+        assert_eq!(
+            Some(dir.join("q.graphql").to_string_lossy().into_owned()),
+            config.requests[1].graphql.as_ref().unwrap().query_file
+        );
+        assert_eq!(
+            vec![
+                Message::File("/tmp/m.bin".to_owned()),
+                Message::File(dir.join("m.bin").to_string_lossy().into_owned())
+            ],
+            config.requests[2].websocket.as_ref().unwrap().messages
+        );
+
+        //but not `--output-file`, which is relative to the current directory, and in which the variables of the request are expanded
+        let request = &config.requests[0];
+        assert_eq!(
+            PathBuf::from("out/1.json"),
+            config.output_file(request, "${out}/${id}.json").unwrap()
+        );
+        assert_eq!(
+            PathBuf::from("/tmp/1.json"),
+            config.output_file(request, "/tmp/${id}.json").unwrap()
+        );
+        let e = config.output_file(request, "${x}.json").unwrap_err();
+        assert_eq!(
+            "--output-file (a): variable `x` is not defined",
+            e.to_string()
+        );
+    }
     //}}}
 }
@@ -0,0 +1,255 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use itertools::Itertools;
+
+//Each variant is mapped to a distinct exit code (see `exit_code()`) so that scripts can react to specific failures.
+//`request` and `path` are the name of the request and the path in the config (e.g. `requests[0].url`) where the error occurred, if known.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("config file (`{}`) not found in `{}` or any of its parents", super::config::CONFIG_FILE_NAMES.join("`, `"), .dir.display())]
+    ConfigNotFound { dir: PathBuf },
+
+    #[error("{}{message}", file_prefix(.file))]
+    Parse {
+        file: Option<PathBuf>,
+        message: String,
+    },
+
+    #[error("included file not found: {}", .file.display())]
+    IncludeNotFound { file: PathBuf },
+
+    #[error("circular include: {}", .chain.iter().map(|p| p.display()).join(" -> "))]
+    CircularInclude { chain: Vec<PathBuf> },
+
+    #[error("{}variable `{name}` is not defined", location(.request, .path))]
+    UndefinedVariable {
+        name: String,
+        request: Option<String>,
+        path: Option<String>,
+    },
+
+    #[error("{}{message}", location(.request, .path))]
+    TypeCast {
+        value: String,
+        message: String,
+        request: Option<String>,
+        path: Option<String>,
+    },
+
+    #[error("{}{message}", location(.request, .path))]
+    InvalidConfig {
+        message: String,
+        request: Option<String>,
+        path: Option<String>,
+    },
+
+    #[error("{}filter `{expr}`: {message}", location(.request, .path))]
+    Filter {
+        expr: String,
+        message: String,
+        request: Option<String>,
+        path: Option<String>,
+    },
+
+    #[error("the config file has {count} error(s)")]
+    Lint { count: usize },
+
+    #[error("no entry found for the index or name: {0}")]
+    RequestNotFound(String),
+
+    #[error("disabled request: {0}")]
+    DisabledRequest(String),
+
+    #[error("no entry found for the group: {0}")]
+    GroupNotFound(String),
+
+    #[error("every request in the group is disabled: {0} (use `--include-disabled` to send them)")]
+    DisabledGroup(String),
+
+    #[error("request `{request}` failed: {}", with_sources(.source))]
+    Http {
+        request: String,
+        source: reqwest::Error,
+    },
+
+    #[error("request `{request}` failed: {source}")]
+    WebSocket {
+        request: String,
+        source: Box<tokio_tungstenite::tungstenite::Error>,
+    },
+
+    #[error("request `{request}` failed: invalid {encoding} body: {source}")]
+    Decode {
+        request: String,
+        encoding: String,
+        source: io::Error,
+    },
+
+    #[error("request `{request}`: expectation(s) not satisfied: `{}`", .unmet.join("`, `"))]
+    Expectation { request: String, unmet: Vec<String> },
+
+    #[error("{}: {source}", .path.display())]
+    Io { path: PathBuf, source: io::Error },
+
+    //Some of the requests sent concurrently failed. `first` is the first failure in the order of definition.
+    #[error("{failed} of {total} requests failed")]
+    PartialFailure {
+        failed: usize,
+        total: usize,
+        first: Box<Error>,
+    },
+
+    #[error("{0}")]
+    Other(String),
+}
+
+fn file_prefix(file: &Option<PathBuf>) -> String {
+    match (file) {
+        Some(f) => format!("{}: ", f.display()),
+        None => String::new(),
+    }
+}
+
+//`reqwest::Error` does not include the underlying cause (e.g. `Connection refused`) in its message
+fn with_sources(e: &dyn std::error::Error) -> String {
+    let mut ret = e.to_string();
+    let mut source = e.source();
+    while let Some(e) = source {
+        ret = format!("{}: {}", ret, e);
+        source = e.source();
+    }
+    ret
+}
+
+fn location(request: &Option<String>, path: &Option<String>) -> String {
+    match (request, path) {
+        (Some(r), Some(p)) => format!("{} ({}): ", p, r),
+        (Some(r), None) => format!("{}: ", r),
+        (None, Some(p)) => format!("{}: ", p),
+        (None, None) => String::new(),
+    }
+}
+
+impl Error {
+    //1 is used for the other errors and 2 is used by `clap` for invalid command-line arguments.
+    pub fn exit_code(&self) -> i32 {
+        match (self) {
+            Error::ConfigNotFound { .. } => 3,
+            Error::Parse { .. } | Error::IncludeNotFound { .. } | Error::CircularInclude { .. } => {
+                4
+            }
+            Error::UndefinedVariable { .. } => 5,
+            Error::TypeCast { .. }
+            | Error::InvalidConfig { .. }
+            | Error::Filter { .. }
+            | Error::Lint { .. } => 6,
+            Error::RequestNotFound(_)
+            | Error::DisabledRequest(_)
+            | Error::GroupNotFound(_)
+            | Error::DisabledGroup(_) => 7,
+            Error::Http { .. } | Error::WebSocket { .. } | Error::Decode { .. } => 8,
+            Error::Io { .. } => 9,
+            Error::Expectation { .. } => 10,
+            Error::PartialFailure { first, .. } => first.exit_code(),
+            Error::Other(_) => 1,
+        }
+    }
+
+    //sets the file of `Parse` if not set yet
+    pub(crate) fn in_file(self, f: &Path) -> Self {
+        match (self) {
+            Error::Parse {
+                file: None,
+                message,
+            } => Error::Parse {
+                file: Some(f.to_owned()),
+                message,
+            },
+            e => e,
+        }
+    }
+}
+
+impl From<regex::Error> for Error {
+    fn from(e: regex::Error) -> Self {
+        Error::Other(e.to_string())
+    }
+}
+
+impl From<bat::error::Error> for Error {
+    fn from(e: bat::error::Error) -> Self {
+        Error::Other(e.to_string())
+    }
+}
+
+//Only an error in the input (e.g. a syntax error, an unknown field or an unexpected end) is a parse error. The others (e.g. an I/O error while serializing) are not the fault of the input.
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        if (e.is_syntax() || e.is_data() || e.is_eof()) {
+            Error::Parse {
+                file: None,
+                message: e.to_string(),
+            }
+        } else {
+            Error::Other(e.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_error {
+    //{{{
+    use super::*;
+
+    struct FailingWriter;
+
+    impl std::io::Write for FailingWriter {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("broken pipe"))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let e = Error::UndefinedVariable {
+            name: "id".to_owned(),
+            request: Some("get_user".to_owned()),
+            path: Some("requests[0].url".to_owned()),
+        };
+        assert_eq!(
+            "requests[0].url (get_user): variable `id` is not defined",
+            e.to_string()
+        );
+        assert_eq!(5, e.exit_code());
+
+        let e = Error::Parse {
+            file: None,
+            message: "expected value".to_owned(),
+        }
+        .in_file(Path::new("ycurl.json"));
+        assert_eq!("ycurl.json: expected value", e.to_string());
+        assert_eq!(4, e.exit_code());
+
+        let e = Error::from(serde_json::from_str::<Vec<u8>>("[1,").unwrap_err());
+        assert_eq!(4, e.exit_code());
+        let e = Error::from(serde_json::from_str::<Vec<u8>>("[\"a\"]").unwrap_err());
+        assert_eq!(4, e.exit_code());
+        //not caused by the input
+        let e = Error::from(serde_json::to_writer(FailingWriter, &[1]).unwrap_err());
+        assert!(matches!(e, Error::Other(_)));
+        assert_eq!(1, e.exit_code());
+
+        let e = Error::DisabledGroup("warm_up".to_owned());
+        assert!(e.to_string().contains("group is disabled: warm_up"));
+        assert_eq!(7, e.exit_code());
+    }
+    //}}}
+}
@@ -0,0 +1,167 @@
+use jaq_core::{
+    load::{Arena, File, Loader},
+    Ctx, Native, RcIter,
+};
+use jaq_json::Val;
+use serde_json::Value;
+use serde_json_path::JsonPath;
+
+use super::error::Error;
+
+//a filter applied to a JSON response body
+//An expression starting with `$` is treated as JSONPath (e.g. `$.items[*].id`), and the others as jq (e.g. `.items[].id`).
+pub struct Filter {
+    expr: String,
+    inner: Inner,
+}
+
+enum Inner {
+    Jq(jaq_core::Filter<Native<Val>>),
+    JsonPath(JsonPath),
+}
+
+impl std::fmt::Debug for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Filter").field(&self.expr).finish()
+    }
+}
+
+fn error(expr: &str, message: String) -> Error {
+    Error::Filter {
+        expr: expr.to_owned(),
+        message,
+        request: None,
+        path: None,
+    }
+}
+
+impl Filter {
+    pub fn new(expr: &str) -> Result<Self, Error> {
+        if (expr.trim_start().starts_with('$')) {
+            return JsonPath::parse(expr.trim())
+                .map(|p| Self {
+                    expr: expr.to_owned(),
+                    inner: Inner::JsonPath(p),
+                })
+                .map_err(|e| error(expr, e.to_string()));
+        }
+
+        let loader = Loader::new(jaq_std::defs().chain(jaq_json::defs()));
+        let arena = Arena::default();
+        let modules = loader
+            .load(
+                &arena,
+                File {
+                    code: expr,
+                    path: (),
+                },
+            )
+            .map_err(|errors| {
+                let messages = errors
+                    .into_iter()
+                    .flat_map(|(_, e)| match (e) {
+                        jaq_core::load::Error::Io(v) => {
+                            v.into_iter().map(|(_, s)| s).collect::<Vec<_>>()
+                        }
+                        jaq_core::load::Error::Lex(v) => v
+                            .into_iter()
+                            .map(|(expect, rest)| unexpected(expect.as_str(), rest))
+                            .collect(),
+                        jaq_core::load::Error::Parse(v) => v
+                            .into_iter()
+                            .map(|(expect, rest)| unexpected(expect.as_str(), rest))
+                            .collect(),
+                    })
+                    .collect::<Vec<_>>();
+                error(expr, messages.join(", "))
+            })?;
+        let filter = jaq_core::Compiler::default()
+            .with_funs(jaq_std::funs().chain(jaq_json::funs()))
+            .compile(modules)
+            .map_err(|errors| {
+                let messages = errors
+                    .into_iter()
+                    .flat_map(|(_, v)| v)
+                    .map(|(name, undefined)| format!("undefined {} `{}`", undefined.as_str(), name))
+                    .collect::<Vec<_>>();
+                error(expr, messages.join(", "))
+            })?;
+        Ok(Self {
+            expr: expr.to_owned(),
+            inner: Inner::Jq(filter),
+        })
+    }
+
+    pub fn expr(&self) -> &str {
+        &self.expr
+    }
+
+    pub fn error(&self, message: &str) -> Error {
+        error(&self.expr, message.to_owned())
+    }
+
+    //returns the outputs of the filter
+    //A jq expression may output any number of values, while JSONPath always outputs an array of the matched nodes.
+    pub fn apply(&self, v: Value) -> Result<Vec<Value>, Error> {
+        match (&self.inner) {
+            Inner::Jq(filter) => {
+                let inputs = RcIter::new(core::iter::empty());
+                filter
+                    .run((Ctx::new([], &inputs), Val::from(v)))
+                    .map(|r| {
+                        r.map(Value::from)
+                            .map_err(|e| error(&self.expr, e.to_string()))
+                    })
+                    .collect()
+            }
+            Inner::JsonPath(path) => Ok(vec![Value::Array(
+                path.query(&v).all().into_iter().cloned().collect(),
+            )]),
+        }
+    }
+}
+
+fn unexpected(expected: &str, rest: &str) -> String {
+    if (rest.is_empty()) {
+        format!("expected {} at the end", expected)
+    } else {
+        format!("expected {} at `{}`", expected, rest)
+    }
+}
+
+#[cfg(test)]
+mod tests_filter {
+    //{{{
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let v = json!({"items": [{"id": 1, "name": "a"}, {"id": 2, "name": "b"}]});
+
+        let filter = Filter::new(".items[].id").unwrap();
+        assert_eq!(vec![json!(1), json!(2)], filter.apply(v.clone()).unwrap());
+
+        let filter = Filter::new(".items | map({name}) | length").unwrap();
+        assert_eq!(vec![json!(2)], filter.apply(v.clone()).unwrap());
+
+        let filter = Filter::new("$.items[*].name").unwrap();
+        assert_eq!(vec![json!(["a", "b"])], filter.apply(v.clone()).unwrap());
+
+        let filter = Filter::new(".items[0].id | error(\"x\")").unwrap();
+        assert!(matches!(filter.apply(v), Err(Error::Filter { .. })));
+
+        assert!(matches!(Filter::new(".items["), Err(Error::Filter { .. })));
+        assert!(matches!(
+            Filter::new("undefined_function"),
+            Err(Error::Filter { .. })
+        ));
+        assert!(matches!(
+            Filter::new("$.items[?"),
+            Err(Error::Filter { .. })
+        ));
+    }
+    //}}}
+}
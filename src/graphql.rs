@@ -0,0 +1,286 @@
+use std::fs;
+
+use itertools::Itertools;
+use serde_json::{json, Map, Value};
+
+use super::config::GraphQL;
+use super::error::Error;
+
+//asks for the root operation types and their fields
+//`ofType` is nested deep enough for types like `[[String!]!]!`.
+pub const INTROSPECTION_QUERY: &str = r#"
+query IntrospectionQuery {
+    __schema {
+        queryType { name }
+        mutationType { name }
+        subscriptionType { name }
+        types {
+            name
+            fields {
+                name
+                description
+                args { name type { ...TypeRef } }
+                type { ...TypeRef }
+            }
+        }
+    }
+}
+
+fragment TypeRef on __Type {
+    kind
+    name
+    ofType { kind name ofType { kind name ofType { kind name ofType { kind name ofType { kind name } } } } }
+}
+"#;
+
+//builds the standard JSON envelope (`{"query": ..., "variables": ..., "operationName": ...}`) sent as a POST body
+pub fn envelope(graphql: &GraphQL) -> Result<Value, Error> {
+    let query = match (&graphql.query, &graphql.query_file) {
+        (Some(query), _) => query.clone(),
+        (None, Some(file)) => fs::read_to_string(file).map_err(|e| Error::Io {
+            path: file.into(),
+            source: e,
+        })?,
+        (None, None) => String::new(),
+    };
+    let mut m = Map::new();
+    m.insert("query".to_owned(), Value::String(query));
+    if let Some(variables) = &graphql.variables {
+        m.insert("variables".to_owned(), Value::Object(variables.clone()));
+    }
+    if let Some(operation_name) = &graphql.operation_name {
+        m.insert(
+            "operationName".to_owned(),
+            Value::String(operation_name.clone()),
+        );
+    }
+    Ok(Value::Object(m))
+}
+
+//returns the `errors` of a GraphQL response, each formatted in a line with its location and path if any
+//`None` if `body` is not a GraphQL response with errors.
+pub fn errors(body: &Value) -> Option<Vec<String>> {
+    let errors = body.get("errors")?.as_array()?;
+    if (errors.is_empty()) {
+        return None;
+    }
+    Some(
+        errors
+            .iter()
+            .map(|e| {
+                let mut s = match (e.get("message")) {
+                    Some(Value::String(message)) => message.clone(),
+                    _ => e.to_string(),
+                };
+                let locations = e
+                    .get("locations")
+                    .and_then(|l| l.as_array())
+                    .map(|l| {
+                        l.iter()
+                            .map(|l| format!("{}:{}", l["line"], l["column"]))
+                            .join(", ")
+                    })
+                    .unwrap_or_default();
+                if (!locations.is_empty()) {
+                    s.push_str(&format!(" (at {})", locations));
+                }
+                if let Some(path) = e.get("path").and_then(|p| p.as_array()) {
+                    let path = path
+                        .iter()
+                        .map(|p| match (p) {
+                            Value::String(s) => s.clone(),
+                            v => v.to_string(),
+                        })
+                        .join(".");
+                    s.push_str(&format!(" (path: {})", path));
+                }
+                s
+            })
+            .collect(),
+    )
+}
+
+//a field of the query, mutation or subscription type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Operation {
+    //`query`, `mutation` or `subscription`
+    pub kind: &'static str,
+    pub name: String,
+    //names and types of the arguments
+    pub args: Vec<(String, String)>,
+    pub type_: String,
+    pub description: Option<String>,
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        if (!self.args.is_empty()) {
+            let args = self
+                .args
+                .iter()
+                .map(|(name, type_)| format!("{}: {}", name, type_))
+                .join(", ");
+            write!(f, "({})", args)?;
+        }
+        write!(f, ": {}", self.type_)
+    }
+}
+
+//formats a type reference like `[User!]!`
+fn type_name(t: &Value) -> String {
+    match (t["kind"].as_str()) {
+        Some("NON_NULL") => format!("{}!", type_name(&t["ofType"])),
+        Some("LIST") => format!("[{}]", type_name(&t["ofType"])),
+        _ => t["name"].as_str().unwrap_or("?").to_owned(),
+    }
+}
+
+//lists the operations in the response to `INTROSPECTION_QUERY`
+pub fn operations(body: &Value) -> Result<Vec<Operation>, Error> {
+    if let Some(errors) = errors(body) {
+        return Err(Error::Other(format!(
+            "introspection failed: {}",
+            errors.join(", ")
+        )));
+    }
+    let schema = &body["data"]["__schema"];
+    if (!schema.is_object()) {
+        return Err(Error::Other(
+            "introspection failed: the response has no `data.__schema`".to_owned(),
+        ));
+    }
+    let types = schema["types"].as_array().cloned().unwrap_or_default();
+    let mut ret = vec![];
+    for (kind, key) in [
+        ("query", "queryType"),
+        ("mutation", "mutationType"),
+        ("subscription", "subscriptionType"),
+    ] {
+        let Some(root) = schema[key]["name"].as_str() else {
+            continue;
+        };
+        let Some(t) = types.iter().find(|t| t["name"] == json!(root)) else {
+            continue;
+        };
+        for field in t["fields"].as_array().into_iter().flatten() {
+            ret.push(Operation {
+                kind,
+                name: field["name"].as_str().unwrap_or_default().to_owned(),
+                args: field["args"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|a| {
+                        (
+                            a["name"].as_str().unwrap_or_default().to_owned(),
+                            type_name(&a["type"]),
+                        )
+                    })
+                    .collect(),
+                type_: type_name(&field["type"]),
+                description: field["description"].as_str().map(|s| s.to_owned()),
+            });
+        }
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests_graphql {
+    //{{{
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let graphql = GraphQL {
+            query: Some("query GetUser($id: ID!) { user(id: $id) { name } }".to_owned()),
+            query_file: None,
+            variables: Some(json!({"id": 1}).as_object().unwrap().clone()),
+            operation_name: Some("GetUser".to_owned()),
+        };
+        assert_eq!(
+            json!({
+                "query": "query GetUser($id: ID!) { user(id: $id) { name } }",
+                "variables": {"id": 1},
+                "operationName": "GetUser",
+            }),
+            envelope(&graphql).unwrap()
+        );
+
+        let body = json!({
+            "data": {"user": null},
+            "errors": [
+                {"message": "not found", "locations": [{"line": 1, "column": 30}], "path": ["user", 0, "name"]},
+                {"message": "forbidden"},
+            ],
+        });
+        assert_eq!(
+            Some(vec![
+                "not found (at 1:30) (path: user.0.name)".to_owned(),
+                "forbidden".to_owned(),
+            ]),
+            errors(&body)
+        );
+        assert_eq!(None, errors(&json!({"data": {}, "errors": []})));
+        assert_eq!(None, errors(&json!({"data": {}})));
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        let non_null = |t: Value| json!({"kind": "NON_NULL", "name": null, "ofType": t});
+        let named = |kind: &str, name: &str| json!({"kind": kind, "name": name, "ofType": null});
+        let body = json!({
+            "data": {
+                "__schema": {
+                    "queryType": {"name": "Query"},
+                    "mutationType": {"name": "Mutation"},
+                    "subscriptionType": null,
+                    "types": [
+                        {
+                            "name": "Query",
+                            "fields": [
+                                {
+                                    "name": "users",
+                                    "description": "Lists users.",
+                                    "args": [{"name": "first", "type": named("SCALAR", "Int")}],
+                                    "type": non_null(json!({"kind": "LIST", "name": null, "ofType": non_null(named("OBJECT", "User"))})),
+                                },
+                            ],
+                        },
+                        {
+                            "name": "Mutation",
+                            "fields": [
+                                {
+                                    "name": "deleteUser",
+                                    "description": null,
+                                    "args": [{"name": "id", "type": non_null(named("SCALAR", "ID"))}],
+                                    "type": named("SCALAR", "Boolean"),
+                                },
+                            ],
+                        },
+                        {"name": "User", "fields": []},
+                    ],
+                },
+            },
+        });
+        let operations = operations(&body).unwrap();
+        assert_eq!(
+            vec![
+                "query users(first: Int): [User!]!",
+                "mutation deleteUser(id: ID!): Boolean",
+            ],
+            operations
+                .iter()
+                .map(|o| format!("{} {}", o.kind, o))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some("Lists users."), operations[0].description.as_deref());
+
+        assert!(super::operations(&json!({"errors": [{"message": "disabled"}]})).is_err());
+        assert!(super::operations(&json!({"data": null})).is_err());
+    }
+    //}}}
+}
@@ -0,0 +1,694 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+    time::Duration,
+};
+
+use reqwest::{
+    header::{HeaderMap, CONTENT_TYPE},
+    Url,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::client::Response;
+use super::config::{HTTPMethod, Protocol, Request};
+use super::error::Error;
+
+//HAR 1.2
+//ref: |http://www.softwareishard.com/blog/har-12-spec/|
+//Only the fields ycurl reads or writes are defined. Unknown fields (e.g. `_initiator` written by browsers) are ignored on import.
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Har {
+    pub log: Log,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Log {
+    pub version: String,
+    pub creator: Creator,
+    #[serde(default)]
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Creator {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    pub started_date_time: String,
+    pub time: f64,
+    pub request: HarRequest,
+    pub response: HarResponse,
+    #[serde(default)]
+    pub cache: Value,
+    pub timings: Timings,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarRequest {
+    pub method: String,
+    pub url: String,
+    pub http_version: String,
+    #[serde(default)]
+    pub cookies: Vec<Value>,
+    #[serde(default)]
+    pub headers: Vec<NameValue>,
+    #[serde(default)]
+    pub query_string: Vec<NameValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<PostData>,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarResponse {
+    pub status: u16,
+    pub status_text: String,
+    pub http_version: String,
+    #[serde(default)]
+    pub cookies: Vec<Value>,
+    #[serde(default)]
+    pub headers: Vec<NameValue>,
+    pub content: Content,
+    #[serde(rename = "redirectURL", default)]
+    pub redirect_url: String,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NameValue {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostData {
+    #[serde(default)]
+    pub mime_type: String,
+    #[serde(default)]
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<NameValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Content {
+    pub size: i64,
+    //the number of bytes saved by the compression
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<i64>,
+    #[serde(default)]
+    pub mime_type: String,
+    #[serde(default)]
+    pub text: String,
+}
+
+//`-1` means the timing is not available.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Timings {
+    #[serde(default = "not_available")]
+    pub blocked: f64,
+    #[serde(default = "not_available")]
+    pub dns: f64,
+    #[serde(default = "not_available")]
+    pub connect: f64,
+    pub send: f64,
+    pub wait: f64,
+    pub receive: f64,
+    #[serde(default = "not_available")]
+    pub ssl: f64,
+}
+
+fn not_available() -> f64 {
+    -1.0
+}
+
+impl Default for Har {
+    fn default() -> Self {
+        Self {
+            log: Log {
+                version: "1.2".to_owned(),
+                creator: Creator {
+                    name: env!("CARGO_PKG_NAME").to_owned(),
+                    version: env!("CARGO_PKG_VERSION").to_owned(),
+                },
+                entries: vec![],
+            },
+        }
+    }
+}
+
+fn to_name_values(headers: &HeaderMap) -> Vec<NameValue> {
+    headers
+        .iter()
+        .map(|(k, v)| NameValue {
+            name: k.to_string(),
+            value: String::from_utf8_lossy(v.as_bytes()).into_owned(),
+        })
+        .collect()
+}
+
+fn milliseconds(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+impl Entry {
+    pub fn new(request: &reqwest::Request, response: &Response) -> Self {
+        let http_version = format!("{:?}", response.version);
+        let status = response.status;
+        let headers = &response.headers;
+        let body = response.text();
+        let timings = response.timings;
+        let optional = |d: Option<Duration>| d.map(milliseconds).unwrap_or(-1.0);
+
+        let post_data = request.body().map(|b| PostData {
+            mime_type: request
+                .headers()
+                .get(CONTENT_TYPE)
+                .map(|v| String::from_utf8_lossy(v.as_bytes()).into_owned())
+                .unwrap_or_default(),
+            text: b
+                .as_bytes()
+                .map(|b| String::from_utf8_lossy(b).into_owned())
+                .unwrap_or_default(),
+            params: vec![],
+        });
+        let request_body_size = post_data.as_ref().map(|p| p.text.len() as i64).unwrap_or(0);
+
+        Self {
+            started_date_time: response.started.to_rfc3339(),
+            time: milliseconds(timings.total()),
+            request: HarRequest {
+                method: request.method().to_string(),
+                url: request.url().to_string(),
+                http_version: http_version.clone(),
+                cookies: vec![],
+                headers: to_name_values(request.headers()),
+                query_string: request
+                    .url()
+                    .query_pairs()
+                    .map(|(k, v)| NameValue {
+                        name: k.into_owned(),
+                        value: v.into_owned(),
+                    })
+                    .collect(),
+                post_data,
+                headers_size: -1,
+                body_size: request_body_size,
+            },
+            response: HarResponse {
+                status: status.as_u16(),
+                status_text: status.canonical_reason().unwrap_or_default().to_owned(),
+                http_version,
+                cookies: vec![],
+                headers: to_name_values(headers),
+                content: Content {
+                    size: response.size() as i64,
+                    compression: response
+                        .encoded
+                        .map(|e| response.size() as i64 - e.size as i64),
+                    mime_type: headers
+                        .get(CONTENT_TYPE)
+                        .map(|v| String::from_utf8_lossy(v.as_bytes()).into_owned())
+                        .unwrap_or_default(),
+                    text: body.clone(),
+                },
+                redirect_url: headers
+                    .get("location")
+                    .map(|v| String::from_utf8_lossy(v.as_bytes()).into_owned())
+                    .unwrap_or_default(),
+                headers_size: -1,
+                body_size: response
+                    .encoded
+                    .map(|e| e.size as i64)
+                    .unwrap_or(response.size() as i64),
+            },
+            cache: Value::Object(Default::default()),
+            timings: Timings {
+                blocked: -1.0,
+                dns: optional(timings.dns),
+                //includes `ssl` as per the spec
+                connect: optional(timings.connect),
+                send: 0.0,
+                wait: milliseconds(
+                    timings
+                        .wait
+                        .saturating_sub(timings.dns.unwrap_or_default())
+                        .saturating_sub(timings.connect.unwrap_or_default()),
+                ),
+                receive: milliseconds(timings.receive),
+                ssl: -1.0,
+            },
+        }
+    }
+}
+
+//appends `entry` to the HAR file, which is created if not exists
+fn read(har_file: &str) -> Result<Har, Error> {
+    let path = Path::new(har_file);
+    let s = fs::read_to_string(path).map_err(|e| Error::Io {
+        path: path.to_owned(),
+        source: e,
+    })?;
+    serde_json::from_str::<Har>(&s).map_err(|e| Error::from(e).in_file(path))
+}
+
+pub fn export(har_file: &str, entry: Entry) -> Result<(), Error> {
+    let mut har = if (Path::new(har_file).is_file()) {
+        read(har_file)?
+    } else {
+        Har::default()
+    };
+    har.log.entries.push(entry);
+    fs::write(har_file, serde_json::to_string_pretty(&har)?).map_err(|e| Error::Io {
+        path: Path::new(har_file).to_owned(),
+        source: e,
+    })
+}
+
+pub fn import(har_file: &str) -> Result<Vec<Request>, Error> {
+    let har = read(har_file)?;
+    entries_to_requests(&har.log.entries).map_err(|e| e.in_file(Path::new(har_file)))
+}
+
+//creates a name like `get_v1_users_123` from the method and the path
+fn request_name(method: &str, url: &Url) -> String {
+    let path = url
+        .path_segments()
+        .map(|l| {
+            l.filter(|s| !s.is_empty())
+                .map(|s| {
+                    s.chars()
+                        .map(|c| if (c.is_ascii_alphanumeric()) { c } else { '_' })
+                        .collect::<String>()
+                })
+                .collect::<Vec<String>>()
+                .join("_")
+        })
+        .unwrap_or_default();
+    if (path.is_empty()) {
+        method.to_lowercase()
+    } else {
+        format!("{}_{}", method.to_lowercase(), path)
+    }
+}
+
+fn entries_to_requests(entries: &[Entry]) -> Result<Vec<Request>, Error> {
+    let mut ret = vec![];
+    let mut name_count: HashMap<String, usize> = HashMap::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let method = match (serde_json::from_value::<HTTPMethod>(Value::String(
+            entry.request.method.to_uppercase(),
+        ))) {
+            Ok(m) => m,
+            Err(_) => {
+                eprintln!(
+                    "skipped an entry with unsupported method: {} {}",
+                    entry.request.method, entry.request.url
+                );
+                continue;
+            }
+        };
+
+        let mut url = Url::parse(&entry.request.url).map_err(|e| Error::Parse {
+            file: None,
+            message: format!(
+                "log.entries[{}].request.url: malformed URL `{}`: {}",
+                i, entry.request.url, e
+            ),
+        })?;
+        let name = {
+            let name = request_name(&entry.request.method, &url);
+            let count = name_count.entry(name.clone()).or_insert(0);
+            *count += 1;
+            if (*count == 1) {
+                name
+            } else {
+                format!("{}_{}", name, count)
+            }
+        };
+
+        //A query with a repeated name (e.g. `?a=1&a=2`) is kept in the URL as it is, as `params` can hold a name only once.
+        let pairs = url
+            .query_pairs()
+            .map(|(k, v)| (k.into_owned(), Value::String(v.into_owned())))
+            .collect::<Vec<_>>();
+        let params = pairs.iter().cloned().collect::<HashMap<String, Value>>();
+        let params = if (params.len() == pairs.len()) {
+            url.set_query(None);
+            params
+        } else {
+            HashMap::new()
+        };
+        url.set_fragment(None);
+
+        //Pseudo-headers of HTTP/2 (e.g. `:authority`) and headers computed by the client are dropped.
+        let headers = entry
+            .request
+            .headers
+            .iter()
+            .filter(|h| {
+                !h.name.starts_with(':')
+                    && !["host", "content-length", "connection"]
+                        .contains(&h.name.to_lowercase().as_str())
+            })
+            .map(|h| (h.name.clone(), h.value.clone()))
+            .collect::<HashMap<String, String>>();
+
+        let body = entry.request.post_data.as_ref().and_then(|p| {
+            //form values with a repeated name are sent as the text
+            let is_unique = p
+                .params
+                .iter()
+                .map(|e| &e.name)
+                .collect::<HashSet<_>>()
+                .len()
+                == p.params.len();
+            if (p.mime_type.contains("application/x-www-form-urlencoded")
+                && !p.params.is_empty()
+                && is_unique)
+            {
+                return Some(Value::Object(
+                    p.params
+                        .iter()
+                        .map(|e| (e.name.clone(), Value::String(e.value.clone())))
+                        .collect(),
+                ));
+            }
+            if (p.text.is_empty() && !p.params.is_empty()) {
+                return Some(Value::String(
+                    form_urlencoded::Serializer::new(String::new())
+                        .extend_pairs(p.params.iter().map(|e| (&e.name, &e.value)))
+                        .finish(),
+                ));
+            }
+            if (p.text.is_empty()) {
+                return None;
+            }
+            match (serde_json::from_str::<Value>(&p.text)) {
+                Ok(v @ Value::Object(_)) => Some(v),
+                _ => Some(Value::String(p.text.clone())),
+            }
+        });
+
+        ret.push(Request {
+            disabled: false,
+            name,
+            description: entry.request.url.clone(),
+            group: None,
+            variables: None,
+            protocol: Protocol::Http,
+            url: url.to_string(),
+            method,
+            headers,
+            params,
+            filter: None,
+            save_to: None,
+            stream: None,
+            websocket: None,
+            graphql: None,
+            body,
+            raw_body: None,
+        });
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests_import {
+    //{{{
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let input = json!({
+            "log": {
+                "version": "1.2",
+                "creator": { "name": "WebInspector", "version": "537.36" },
+                "pages": [],
+                "entries": [
+                    {
+                        "_initiator": { "type": "script" },
+                        "startedDateTime": "2023-08-01T00:00:00.000Z",
+                        "time": 12.5,
+                        "request": {
+                            "method": "GET",
+                            "url": "https://example.com/v1/users?page=2",
+                            "httpVersion": "http/2.0",
+                            "headers": [
+                                { "name": ":authority", "value": "example.com" },
+                                { "name": "accept", "value": "application/json" }
+                            ],
+                            "queryString": [ { "name": "page", "value": "2" } ],
+                            "cookies": [],
+                            "headersSize": -1,
+                            "bodySize": 0
+                        },
+                        "response": {
+                            "status": 200,
+                            "statusText": "",
+                            "httpVersion": "http/2.0",
+                            "headers": [],
+                            "cookies": [],
+                            "content": { "size": 2, "mimeType": "application/json", "text": "[]" },
+                            "redirectURL": "",
+                            "headersSize": -1,
+                            "bodySize": -1
+                        },
+                        "cache": {},
+                        "timings": { "send": 0.1, "wait": 10.0, "receive": 2.4 }
+                    },
+                    {
+                        "startedDateTime": "2023-08-01T00:00:01.000Z",
+                        "time": 20.0,
+                        "request": {
+                            "method": "POST",
+                            "url": "https://example.com/v1/users",
+                            "httpVersion": "http/2.0",
+                            "headers": [
+                                { "name": "content-type", "value": "application/json" },
+                                { "name": "content-length", "value": "16" }
+                            ],
+                            "queryString": [],
+                            "cookies": [],
+                            "postData": { "mimeType": "application/json", "text": "{\"name\":\"Mike\"}" },
+                            "headersSize": -1,
+                            "bodySize": 16
+                        },
+                        "response": {
+                            "status": 201,
+                            "statusText": "Created",
+                            "httpVersion": "http/2.0",
+                            "headers": [],
+                            "cookies": [],
+                            "content": { "size": 0, "mimeType": "" },
+                            "redirectURL": "",
+                            "headersSize": -1,
+                            "bodySize": 0
+                        },
+                        "cache": {},
+                        "timings": { "send": 0.1, "wait": 18.0, "receive": 1.9 }
+                    },
+                    {
+                        "startedDateTime": "2023-08-01T00:00:02.000Z",
+                        "time": 20.0,
+                        "request": {
+                            "method": "GET",
+                            "url": "https://example.com/v1/users?page=3",
+                            "httpVersion": "http/2.0",
+                            "headers": [],
+                            "queryString": [ { "name": "page", "value": "3" } ],
+                            "cookies": [],
+                            "headersSize": -1,
+                            "bodySize": 0
+                        },
+                        "response": {
+                            "status": 200,
+                            "statusText": "",
+                            "httpVersion": "http/2.0",
+                            "headers": [],
+                            "cookies": [],
+                            "content": { "size": 2, "mimeType": "application/json", "text": "[]" },
+                            "redirectURL": "",
+                            "headersSize": -1,
+                            "bodySize": -1
+                        },
+                        "cache": {},
+                        "timings": { "send": 0.1, "wait": 10.0, "receive": 2.4 }
+                    }
+                ]
+            }
+        });
+
+        let har = serde_json::from_value::<Har>(input).unwrap();
+        let requests = entries_to_requests(&har.log.entries);
+        println!("{:?}", requests);
+        assert!(requests.is_ok());
+        let requests = requests.unwrap();
+        assert_eq!(3, requests.len());
+
+        assert_eq!("get_v1_users", requests[0].name);
+        assert_eq!("https://example.com/v1/users", requests[0].url);
+        assert_eq!(HTTPMethod::Get, requests[0].method);
+        assert_eq!(1, requests[0].headers.len());
+        assert_eq!("application/json", requests[0].headers["accept"]);
+        assert_eq!(json!("2"), requests[0].params["page"]);
+        assert_eq!(None, requests[0].body);
+
+        assert_eq!("post_v1_users", requests[1].name);
+        assert_eq!(HTTPMethod::Post, requests[1].method);
+        assert_eq!(1, requests[1].headers.len());
+        assert_eq!(Some(json!({"name": "Mike"})), requests[1].body);
+
+        assert_eq!("get_v1_users_2", requests[2].name);
+        assert_eq!(json!("3"), requests[2].params["page"]);
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        let input = json!({
+            "log": {
+                "version": "1.2",
+                "creator": { "name": "Firefox", "version": "116.0" },
+                "entries": [
+                    {
+                        "startedDateTime": "2023-08-01T00:00:00.000Z",
+                        "time": 1.0,
+                        "request": {
+                            "method": "OPTIONS",
+                            "url": "https://example.com/",
+                            "httpVersion": "HTTP/1.1",
+                            "headersSize": -1,
+                            "bodySize": 0
+                        },
+                        "response": {
+                            "status": 204,
+                            "statusText": "No Content",
+                            "httpVersion": "HTTP/1.1",
+                            "content": { "size": 0 },
+                            "headersSize": -1,
+                            "bodySize": 0
+                        },
+                        "timings": { "send": 0, "wait": 1, "receive": 0 }
+                    },
+                    {
+                        "startedDateTime": "2023-08-01T00:00:00.000Z",
+                        "time": 1.0,
+                        "request": {
+                            "method": "POST",
+                            "url": "https://example.com/login",
+                            "httpVersion": "HTTP/1.1",
+                            "postData": {
+                                "mimeType": "application/x-www-form-urlencoded",
+                                "text": "user=mike&password=abc",
+                                "params": [
+                                    { "name": "user", "value": "mike" },
+                                    { "name": "password", "value": "abc" }
+                                ]
+                            },
+                            "headersSize": -1,
+                            "bodySize": 22
+                        },
+                        "response": {
+                            "status": 302,
+                            "statusText": "Found",
+                            "httpVersion": "HTTP/1.1",
+                            "content": { "size": 0 },
+                            "headersSize": -1,
+                            "bodySize": 0
+                        },
+                        "timings": { "send": 0, "wait": 1, "receive": 0 }
+                    }
+                ]
+            }
+        });
+
+        let har = serde_json::from_value::<Har>(input).unwrap();
+        let requests = entries_to_requests(&har.log.entries).unwrap();
+        assert_eq!(1, requests.len());
+        assert_eq!("post_login", requests[0].name);
+        assert_eq!(
+            Some(json!({"user": "mike", "password": "abc"})),
+            requests[0].body
+        );
+    }
+
+    #[test]
+    // #[ignore]
+    fn test03() {
+        let entry = |request: Value| {
+            json!({
+                "startedDateTime": "2023-08-01T00:00:00.000Z",
+                "time": 1.0,
+                "request": request,
+                "response": {
+                    "status": 200,
+                    "statusText": "OK",
+                    "httpVersion": "HTTP/1.1",
+                    "content": { "size": 0 },
+                    "headersSize": -1,
+                    "bodySize": 0
+                },
+                "timings": { "send": 0, "wait": 1, "receive": 0 }
+            })
+        };
+        let input = json!({
+            "log": {
+                "version": "1.2",
+                "creator": { "name": "Firefox", "version": "116.0" },
+                "entries": [
+                    entry(json!({
+                        "method": "GET",
+                        "url": "https://example.com/items?tag=a&tag=b&page=2",
+                        "httpVersion": "HTTP/1.1",
+                        "headersSize": -1,
+                        "bodySize": 0
+                    })),
+                    entry(json!({
+                        "method": "POST",
+                        "url": "https://example.com/items",
+                        "httpVersion": "HTTP/1.1",
+                        "postData": {
+                            "mimeType": "application/x-www-form-urlencoded",
+                            "text": "",
+                            "params": [
+                                { "name": "tag", "value": "a" },
+                                { "name": "tag", "value": "b c" }
+                            ]
+                        },
+                        "headersSize": -1,
+                        "bodySize": 13
+                    }))
+                ]
+            }
+        });
+
+        //repeated names are kept rather than collapsed
+        let har = serde_json::from_value::<Har>(input).unwrap();
+        let requests = entries_to_requests(&har.log.entries).unwrap();
+        assert_eq!(
+            "https://example.com/items?tag=a&tag=b&page=2",
+            requests[0].url
+        );
+        assert!(requests[0].params.is_empty());
+        assert_eq!(Some(json!("tag=a&tag=b+c")), requests[1].body);
+    }
+    //}}}
+}
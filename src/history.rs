@@ -0,0 +1,616 @@
+use std::{collections::HashMap, fs, path::Path, str::FromStr};
+
+use chrono::DateTime;
+use reqwest::Url;
+use serde_json::Value;
+
+use super::client::Client;
+use super::config::{CLIOptions, Config, HTTPMethod, Protocol, Request};
+use super::error::Error;
+use super::logger::{self, Record, RecordedRequest};
+use super::paint;
+use super::redact::REDACTED;
+
+//a condition on the status of the entries listed by `ycurl history`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusFilter {
+    //e.g. `404`
+    Code(u16),
+    //e.g. `5xx`, which holds `5`
+    Class(u16),
+    //the requests which failed without a response
+    Error,
+}
+
+impl FromStr for StatusFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("expected a status like `404` or `5xx`, or `error`: `{}`", s);
+        if (s == "error") {
+            return Ok(StatusFilter::Error);
+        }
+        if let Some(class) = s.strip_suffix("xx") {
+            return match (class.parse::<u16>()) {
+                Ok(n) if ((1..=5).contains(&n)) => Ok(StatusFilter::Class(n)),
+                _ => Err(error()),
+            };
+        }
+        match (s.parse::<u16>()) {
+            Ok(n) if ((100..=599).contains(&n)) => Ok(StatusFilter::Code(n)),
+            _ => Err(error()),
+        }
+    }
+}
+
+impl StatusFilter {
+    fn matches(&self, record: &Record) -> bool {
+        let status = record.response.as_ref().map(|r| r.status);
+        match (self, status) {
+            (StatusFilter::Code(n), Some(status)) => (status == *n),
+            (StatusFilter::Class(n), Some(status)) => (status / 100 == *n),
+            (StatusFilter::Error, None) => true,
+            _ => false,
+        }
+    }
+}
+
+//reads the records in the structured log with their IDs, which are the line numbers starting from 1
+//Malformed lines are skipped with a warning.
+pub fn load(path: &Path) -> Result<Vec<(usize, Record)>, Error> {
+    let content = match (fs::read_to_string(path)) {
+        Ok(s) => s,
+        Err(e) if (e.kind() == std::io::ErrorKind::NotFound) => String::new(),
+        Err(e) => {
+            return Err(Error::Io {
+                path: path.to_owned(),
+                source: e,
+            })
+        }
+    };
+    let mut ret = vec![];
+    for (i, line) in content.lines().enumerate() {
+        if (line.trim().is_empty()) {
+            continue;
+        }
+        //a broken line (e.g. written halfway) does not hide the other records
+        match (serde_json::from_str::<Record>(line)) {
+            Ok(record) => ret.push((i + 1, record)),
+            Err(e) => eprintln!(
+                "skipped a malformed line in {}: line {}: {}",
+                path.display(),
+                i + 1,
+                e
+            ),
+        }
+    }
+    Ok(ret)
+}
+
+//returns the record with the ID in the structured log
+pub fn find(id: usize) -> Result<Record, Error> {
+    load(&logger::jsonl_path()?)?
+        .into_iter()
+        .find(|(i, _)| *i == id)
+        .map(|(_, record)| record)
+        .ok_or_else(|| Error::RequestNotFound(format!("{} (history)", id)))
+}
+
+//prints the most recent `limit` records which satisfy the conditions, the oldest first
+pub fn print(
+    records: &[(usize, Record)],
+    name: Option<&str>,
+    status: Option<StatusFilter>,
+    limit: usize,
+) {
+    let records = records
+        .iter()
+        .filter(|(_, r)| name.is_none_or(|name| r.name == name))
+        .filter(|(_, r)| status.is_none_or(|s| s.matches(r)))
+        .collect::<Vec<_>>();
+    let width = records
+        .last()
+        .map(|(id, _)| id.to_string().len())
+        .unwrap_or(0);
+    for (id, r) in &records[records.len().saturating_sub(limit)..] {
+        let timestamp = DateTime::parse_from_rfc3339(&r.timestamp)
+            .map(|t| t.format("%Y/%m/%d %H:%M:%S").to_string())
+            .unwrap_or(r.timestamp.clone());
+        let status = match (&r.response) {
+            Some(response) if (response.status < 400) => paint("032", &response.status.to_string()),
+            Some(response) => paint("031", &response.status.to_string()),
+            None => paint("031", "ERR"),
+        };
+        let duration = r
+            .duration
+            .map(|d| format!("{:.0}ms", d))
+            .unwrap_or("-".to_owned());
+        println!(
+            "{:>width$}  {}  {}  {:>7}  {}  {} {}",
+            id,
+            paint("090", &timestamp),
+            status,
+            duration,
+            paint("1", &r.name),
+            r.request.method,
+            r.request.url,
+            width = width
+        );
+    }
+}
+
+//whether any part of the recorded request has been redacted in the log
+fn is_redacted(request: &RecordedRequest) -> bool {
+    request.url.contains(REDACTED)
+        || request
+            .headers
+            .values()
+            .any(|v| v.as_str().is_some_and(|v| v.contains(REDACTED)))
+        || request.body.as_ref().is_some_and(|b| b.contains(REDACTED))
+}
+
+//replaces the values hidden in the `name=value&...` pairs of `recorded` with those of the same names in `current`
+//The n-th pair of a name takes the value of the n-th pair of the name in `current`. Returns the name not found in `current` on failure.
+fn restore_form(recorded: &str, current: &str) -> Result<String, String> {
+    let current = current
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .collect::<Vec<_>>();
+    let mut seen = HashMap::new();
+    recorded
+        .split('&')
+        .map(|pair| {
+            let Some((k, v)) = pair.split_once('=') else {
+                return Ok(pair.to_owned());
+            };
+            let n = seen.entry(k).or_insert(0);
+            *n += 1;
+            if (!v.contains(REDACTED)) {
+                return Ok(pair.to_owned());
+            }
+            current
+                .iter()
+                .filter(|(name, _)| *name == k)
+                .nth(*n - 1)
+                .map(|(_, v)| format!("{}={}", k, v))
+                .ok_or(k.to_owned())
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(|l| l.join("&"))
+}
+
+//replaces the values hidden in the JSON `recorded` with those at the same paths in `current`
+//Returns the path not found in `current` on failure.
+fn restore_json(recorded: &mut Value, current: &Value, path: &str) -> Result<(), String> {
+    match (recorded) {
+        Value::Object(o) => {
+            for (k, v) in o.iter_mut() {
+                restore_json(
+                    v,
+                    current.get(k).unwrap_or(&Value::Null),
+                    &format!("{}.{}", path, k),
+                )?;
+            }
+        }
+        Value::Array(l) => {
+            for (i, v) in l.iter_mut().enumerate() {
+                restore_json(
+                    v,
+                    current.get(i).unwrap_or(&Value::Null),
+                    &format!("{}[{}]", path, i),
+                )?;
+            }
+        }
+        Value::String(s) if (s.contains(REDACTED)) => {
+            if (current.is_null()) {
+                return Err(path.to_owned());
+            }
+            *recorded = current.clone();
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+//replaces the values hidden in `recorded` (the URL, the query parameters, the headers and the body) with those in `current` (the request rebuilt from the config file), leaving the other parts as recorded
+//Returns the part not found in `current` on failure.
+fn restore(
+    recorded: &RecordedRequest,
+    current: &RecordedRequest,
+) -> Result<RecordedRequest, String> {
+    let mut ret = recorded.clone();
+
+    let split = |url: &str| -> (String, Option<String>) {
+        let url = url.split_once('#').map(|(u, _)| u).unwrap_or(url);
+        match (url.split_once('?')) {
+            Some((path, query)) => (path.to_owned(), Some(query.to_owned())),
+            None => (url.to_owned(), None),
+        }
+    };
+    if (recorded.url.contains(REDACTED)) {
+        let (mut path, query) = split(&recorded.url);
+        let (current_path, current_query) = split(&current.url);
+        if (path.contains(REDACTED)) {
+            path = current_path;
+        }
+        ret.url = match (query) {
+            Some(query) => format!(
+                "{}?{}",
+                path,
+                restore_form(&query, &current_query.unwrap_or_default())
+                    .map_err(|k| format!("the query parameter `{}`", k))?
+            ),
+            None => path,
+        };
+    }
+
+    for (k, v) in ret.headers.iter_mut() {
+        if (v.as_str().is_some_and(|v| v.contains(REDACTED))) {
+            *v = current
+                .headers
+                .get(k)
+                .cloned()
+                .ok_or(format!("the header `{}`", k))?;
+        }
+    }
+
+    if let Some(body) = recorded.body.as_ref().filter(|b| b.contains(REDACTED)) {
+        let current_body = current.body.clone().unwrap_or_default();
+        ret.body_base64 = current.body_base64;
+        ret.body = Some(if (current.body_base64) {
+            //a binary body cannot be restored in part
+            current_body
+        } else if let Ok(mut v @ (Value::Object(_) | Value::Array(_))) =
+            serde_json::from_str::<Value>(body)
+        {
+            let current_value = serde_json::from_str::<Value>(&current_body).unwrap_or_default();
+            restore_json(&mut v, &current_value, "")
+                .map_err(|path| format!("`{}` in the body", path))?;
+            //the body in the log has been pretty-printed
+            if (v == current_value) {
+                current_body
+            } else {
+                v.to_string()
+            }
+        } else if (!body.contains(char::is_whitespace) && body.contains('=')) {
+            restore_form(body, &current_body)
+                .map_err(|k| format!("the field `{}` in the body", k))?
+        } else {
+            //a secret variable in a text body cannot be located, so the whole body is taken
+            current_body
+        });
+    }
+    Ok(ret)
+}
+
+//builds a config with the recorded request as its only request
+//The request is sent exactly as recorded: the URL already contains the query parameters, and the body is sent as it is.
+//The values hidden in the log are taken from the request rebuilt from `original` (the config file from which it was sent, as it is now), so their current values (e.g. a refreshed token) are sent, while the other parts are sent as recorded. `original` also gives the redaction rules.
+pub fn to_config(
+    record: &Record,
+    cli_options: CLIOptions,
+    original: Option<&Config>,
+) -> Result<Config, Error> {
+    let recorded = if (is_redacted(&record.request)) {
+        let error = |reason: String| {
+            Error::Other(format!(
+                "cannot replay `{}`: its secrets are hidden in the log, and {}",
+                record.name, reason
+            ))
+        };
+        let original = original.ok_or_else(|| {
+            error(format!(
+                "`{}` cannot be loaded to restore them",
+                record.config
+            ))
+        })?;
+        let request = original
+            .request(&record.name)
+            .map_err(|_| error(format!("`{}` no longer has the request", record.config)))?;
+        let current = Client::new(original, request)?
+            .request()
+            .map(|req| RecordedRequest::new(&req))
+            .ok_or_else(|| {
+                error(format!(
+                    "the request in `{}` cannot be rebuilt",
+                    record.config
+                ))
+            })?;
+        restore(&record.request, &current).map_err(|part| {
+            error(format!(
+                "the request in `{}` no longer has {}",
+                record.config, part
+            ))
+        })?
+    } else {
+        record.request.clone()
+    };
+
+    if (Url::parse(&recorded.url).is_ok_and(|u| matches!(u.scheme(), "ws" | "wss"))) {
+        return Err(Error::Other(format!(
+            "cannot replay `{}`: a WebSocket request cannot be replayed",
+            record.name
+        )));
+    }
+    //a body which is not UTF-8 is sent as the bytes recorded
+    let raw_body = if (recorded.body_base64) {
+        Some(recorded.body_bytes().ok_or_else(|| {
+            Error::Other(format!(
+                "cannot replay `{}`: the body in the history is not valid base64",
+                record.name
+            ))
+        })?)
+    } else {
+        None
+    };
+
+    let method = serde_json::from_value::<HTTPMethod>(Value::String(recorded.method.clone()))
+        .map_err(|_| {
+            Error::Other(format!(
+                "unsupported method in the history: `{}`",
+                recorded.method
+            ))
+        })?;
+    let headers = recorded
+        .headers
+        .iter()
+        .map(|(k, v)| (k.clone(), v.as_str().unwrap_or_default().to_owned()))
+        .collect::<HashMap<String, String>>();
+
+    let request = Request {
+        disabled: false,
+        name: record.name.clone(),
+        description: String::new(),
+        group: None,
+        variables: None,
+        protocol: Protocol::Http,
+        url: recorded.url,
+        method,
+        headers,
+        params: HashMap::new(),
+        filter: None,
+        save_to: None,
+        stream: None,
+        websocket: None,
+        graphql: None,
+        body: recorded
+            .body
+            .filter(|_| raw_body.is_none())
+            .map(Value::String),
+        raw_body,
+    };
+    Ok(Config {
+        schema: None,
+        description: String::new(),
+        include: vec![],
+        cli_options,
+        base_url: String::new(),
+        variables: Default::default(),
+        default_headers: HashMap::new(),
+        redact: original.map(|c| c.redact.clone()).unwrap_or_default(),
+        requests: vec![request],
+        secrets: original.map(|c| c.secrets.clone()).unwrap_or_default(),
+        dir: Path::new(&record.config)
+            .parent()
+            .map(|p| p.to_owned())
+            .unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests_history {
+    //{{{
+    use super::*;
+
+    use serde_json::json;
+
+    use super::super::redact::Redactor;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        assert_eq!(Ok(StatusFilter::Code(404)), "404".parse());
+        assert_eq!(Ok(StatusFilter::Class(5)), "5xx".parse());
+        assert_eq!(Ok(StatusFilter::Error), "error".parse());
+        assert!("6xx".parse::<StatusFilter>().is_err());
+        assert!("99".parse::<StatusFilter>().is_err());
+
+        let record = |name: &str, status: Option<u16>| {
+            json!({
+                "timestamp": "2024-01-01T12:00:00+09:00",
+                "config": "/home/user/ycurl.json",
+                "name": name,
+                "request": {
+                    "method": "POST",
+                    "url": "http://localhost:3000/users?dry_run=true",
+                    "headers": {"content-type": "application/json"},
+                    "body": "{\"name\": \"Mike\"}",
+                },
+                "response": status.map(|status| json!({
+                    "status": status,
+                    "url": "http://localhost:3000/users",
+                    "headers": {},
+                    "body": "",
+                    "truncated": false,
+                    "size": 0,
+                })),
+                "duration": status.map(|_| 1.5),
+            })
+            .to_string()
+        };
+        let path =
+            std::env::temp_dir().join(format!("ycurl_tests_history_01_{}", std::process::id()));
+        fs::write(
+            &path,
+            [
+                record("a", Some(201)),
+                record("b", Some(503)),
+                String::new(),
+                record("a", None),
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+        let records = load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            vec![1, 2, 4],
+            records.iter().map(|(id, _)| *id).collect::<Vec<_>>()
+        );
+
+        let matches = |status: &str| {
+            let status = status.parse::<StatusFilter>().unwrap();
+            records
+                .iter()
+                .filter(|(_, r)| status.matches(r))
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec![1], matches("2xx"));
+        assert_eq!(vec![2], matches("503"));
+        assert_eq!(vec![4], matches("error"));
+
+        //a record written before the options were recorded
+        assert_eq!(CLIOptions::default(), records[0].1.cli_options);
+        let mut record = records[0].1.clone();
+        record.cli_options.compressed = true;
+        let v = serde_json::to_string(&record).unwrap();
+        assert!(
+            serde_json::from_str::<Record>(&v)
+                .unwrap()
+                .cli_options
+                .compressed
+        );
+
+        let config = to_config(&records[0].1, CLIOptions::default(), None).unwrap();
+        let request = &config.requests[0];
+        assert_eq!("a", request.name);
+        assert_eq!(HTTPMethod::Post, request.method);
+        assert_eq!("http://localhost:3000/users?dry_run=true", request.url);
+        assert!(request.params.is_empty());
+        assert_eq!("application/json", request.headers["content-type"]);
+        assert_eq!(Some(json!("{\"name\": \"Mike\"}")), request.body);
+
+        let mut record = records[0].1.clone();
+        record.request.method = "OPTIONS".to_owned();
+        assert!(to_config(&record, CLIOptions::default(), None).is_err());
+
+        //a body which is not UTF-8 is replayed as the bytes
+        let mut record = records[0].1.clone();
+        record.request.body = Some("iVBOR/8=".to_owned());
+        record.request.body_base64 = true;
+        let config = to_config(&record, CLIOptions::default(), None).unwrap();
+        assert_eq!(None, config.requests[0].body);
+        assert_eq!(
+            Some(vec![0x89, b'P', b'N', b'G', 0xff]),
+            config.requests[0].raw_body
+        );
+
+        let mut record = records[0].1.clone();
+        record.request.url = "ws://localhost:3000/ws".to_owned();
+        let e = to_config(&record, CLIOptions::default(), None).unwrap_err();
+        assert!(e.to_string().contains("WebSocket"));
+
+        let path = std::env::temp_dir().join(format!(
+            "ycurl_tests_history_01_broken_{}",
+            std::process::id()
+        ));
+        fs::write(
+            &path,
+            [
+                "{}",
+                &serde_json::to_string(&records[0].1).unwrap(),
+                "{\"timesta",
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+        let result = load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            vec![2],
+            result
+                .unwrap()
+                .iter()
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        let dir =
+            std::env::temp_dir().join(format!("ycurl_tests_history_02_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config_file = dir.join("ycurl.json");
+        let write_config = |token: &str, k: u32| {
+            fs::write(
+                &config_file,
+                format!(
+                    r#"{{
+                        "base_url": "http://localhost",
+                        "variables": {{"token": {{"value": "{}", "secret": true}}}},
+                        "requests": [
+                            {{
+                                "name": "a",
+                                "url": "/a",
+                                "method": "POST",
+                                "headers": {{"Authorization": "Bearer ${{token}}", "X-K": "{}"}},
+                                "params": {{"access_token": "${{token}}", "k": "{}"}},
+                                "body": "{{\"k\": {}, \"password\": \"${{token}}\"}}"
+                            }},
+                            {{"name": "b", "url": "/b", "method": "POST"}}
+                        ]
+                    }}"#,
+                    token, k, k, k
+                ),
+            )
+            .unwrap();
+            Config::new(&config_file.to_string_lossy(), None).unwrap()
+        };
+
+        //the record as written to the log
+        let original = write_config("xyz", 1);
+        let request = original.request("a").unwrap();
+        let req = Client::new(&original, request).unwrap().request();
+        let record = Record::new(
+            &config_file.to_string_lossy(),
+            &original.cli_options,
+            request,
+            req.as_ref(),
+        )
+        .redact(&Redactor::new(&original));
+        assert_eq!(json!("[REDACTED]"), record.request.headers["authorization"]);
+        assert!(!serde_json::to_string(&record).unwrap().contains("xyz"));
+
+        //the secrets are restored from the config file
+        let config = to_config(&record, CLIOptions::default(), Some(&original)).unwrap();
+        let request = &config.requests[0];
+        assert_eq!("Bearer xyz", request.headers["authorization"]);
+        assert!(request.url.contains("access_token=xyz"));
+        assert_eq!(Some(json!(r#"{"k": 1, "password": "xyz"}"#)), request.body);
+        assert_eq!(original.secrets, config.secrets);
+
+        //after a variable edit, the hidden values are the current ones, while the others are as recorded
+        let edited = write_config("rotated", 2);
+        let config = to_config(&record, CLIOptions::default(), Some(&edited)).unwrap();
+        let request = &config.requests[0];
+        assert_eq!("Bearer rotated", request.headers["authorization"]);
+        assert_eq!("1", request.headers["x-k"]);
+        assert!(request.url.contains("access_token=rotated"));
+        assert!(request.url.contains("k=1"));
+        assert_eq!(Some(json!(r#"{"k":1,"password":"rotated"}"#)), request.body);
+
+        //not if a hidden value no longer exists
+        let mut record = record;
+        record.name = "b".to_owned();
+        let e = to_config(&record, CLIOptions::default(), Some(&edited)).unwrap_err();
+        assert!(e
+            .to_string()
+            .contains("no longer has the query parameter `access_token`"));
+        assert!(to_config(&record, CLIOptions::default(), None).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    //}}}
+}
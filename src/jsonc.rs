@@ -0,0 +1,346 @@
+use std::collections::HashMap;
+
+//converts JSON with comments (JSONC) to plain JSON
+//Supported extensions:
+// - `# ...` and `// ...` comments (both whole-line and inline)
+// - `/* ... */` comments
+// - trailing commas in arrays and objects
+//Comments and trailing commas are replaced with spaces instead of being removed so that the line and column numbers reported by `serde_json` point to the original source.
+//Columns are preserved in bytes as `serde_json` counts them in bytes.
+pub fn strip(s: &str) -> Result<String, String> {
+    let without_comments = strip_comments(s)?;
+    Ok(strip_trailing_commas(&without_comments))
+}
+
+fn blank(c: char, buf: &mut String) {
+    if (c == '\n' || c == '\r') {
+        buf.push(c);
+    } else {
+        (0..c.len_utf8()).for_each(|_| buf.push(' '));
+    }
+}
+
+fn strip_comments(s: &str) -> Result<String, String> {
+    let mut ret = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    let mut line = 1;
+    let mut column = 1;
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        let (start_line, start_column) = (line, column);
+        let mut advance = |c: char| {
+            if (c == '\n') {
+                line += 1;
+                column = 1;
+            } else {
+                column += c.len_utf8();
+            }
+        };
+        advance(c);
+
+        if (in_string) {
+            ret.push(c);
+            if (c == '\\') {
+                if let Some(next) = chars.next() {
+                    advance(next);
+                    ret.push(next);
+                }
+            } else if (c == '"') {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                ret.push(c);
+            }
+            ('#', _) | ('/', Some('/')) => {
+                blank(c, &mut ret);
+                while let Some(&next) = chars.peek() {
+                    if (next == '\n') {
+                        break;
+                    }
+                    advance(next);
+                    blank(next, &mut ret);
+                    chars.next();
+                }
+            }
+            ('/', Some('*')) => {
+                blank(c, &mut ret);
+                let star = chars.next().unwrap();
+                advance(star);
+                blank(star, &mut ret);
+                let mut terminated = false;
+                while let Some(next) = chars.next() {
+                    advance(next);
+                    blank(next, &mut ret);
+                    if (next == '*' && chars.peek() == Some(&'/')) {
+                        let slash = chars.next().unwrap();
+                        advance(slash);
+                        blank(slash, &mut ret);
+                        terminated = true;
+                        break;
+                    }
+                }
+                if (!terminated) {
+                    return Err(format!(
+                        "unterminated comment at line {} column {}",
+                        start_line, start_column
+                    ));
+                }
+            }
+            _ => ret.push(c),
+        }
+    }
+    Ok(ret)
+}
+
+//assumes comments have already been removed
+fn strip_trailing_commas(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        if (in_string) {
+            if (escaped) {
+                escaped = false;
+            } else if (c == '\\') {
+                escaped = true;
+            } else if (c == '"') {
+                in_string = false;
+            }
+            ret.push(c);
+            continue;
+        }
+        match (c) {
+            '"' => {
+                in_string = true;
+                ret.push(c);
+            }
+            ',' => {
+                let next = s[i + 1..].chars().find(|c| !c.is_whitespace());
+                if (matches!(next, Some(']') | Some('}'))) {
+                    ret.push(' ');
+                } else {
+                    ret.push(c);
+                }
+            }
+            _ => ret.push(c),
+        }
+    }
+    ret
+}
+
+//returns the positions (line, column) of the values in `s`, keyed by their paths like `requests[0].headers.Accept`
+//`s` shall be a JSON without comments (e.g. the output of `strip()`). Scanning stops at the first syntax error.
+pub fn locate(s: &str) -> HashMap<String, (usize, usize)> {
+    let mut locator = Locator {
+        s,
+        i: 0,
+        offsets: HashMap::new(),
+    };
+    locator.value(String::new());
+
+    let line_starts = std::iter::once(0)
+        .chain(s.match_indices('\n').map(|(i, _)| i + 1))
+        .collect::<Vec<usize>>();
+    locator
+        .offsets
+        .into_iter()
+        .map(|(path, offset)| {
+            let line = line_starts.partition_point(|&start| start <= offset);
+            (path, (line, offset - line_starts[line - 1] + 1))
+        })
+        .collect()
+}
+
+struct Locator<'a> {
+    s: &'a str,
+    i: usize,
+    offsets: HashMap<String, usize>,
+}
+
+impl Locator<'_> {
+    fn skip_whitespaces(&mut self) {
+        while (self.i < self.s.len() && self.s.as_bytes()[self.i].is_ascii_whitespace()) {
+            self.i += 1;
+        }
+    }
+
+    fn consume(&mut self, c: u8) -> Option<()> {
+        self.skip_whitespaces();
+        if (self.s.as_bytes().get(self.i) == Some(&c)) {
+            self.i += 1;
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let start = self.i;
+        self.i += 1;
+        while (self.i < self.s.len()) {
+            match (self.s.as_bytes()[self.i]) {
+                b'\\' => self.i += 2,
+                b'"' => {
+                    self.i += 1;
+                    return serde_json::from_str(&self.s[start..self.i]).ok();
+                }
+                _ => self.i += 1,
+            }
+        }
+        None
+    }
+
+    fn value(&mut self, path: String) -> Option<()> {
+        self.skip_whitespaces();
+        self.offsets.insert(path.clone(), self.i);
+        match (self.s.as_bytes().get(self.i)?) {
+            b'{' => {
+                self.i += 1;
+                if (self.consume(b'}').is_some()) {
+                    return Some(());
+                }
+                loop {
+                    self.skip_whitespaces();
+                    let key = self.string()?;
+                    self.consume(b':')?;
+                    if (path.is_empty()) {
+                        self.value(key)?;
+                    } else {
+                        self.value(format!("{}.{}", path, key))?;
+                    }
+                    if (self.consume(b',').is_none()) {
+                        return self.consume(b'}');
+                    }
+                }
+            }
+            b'[' => {
+                self.i += 1;
+                if (self.consume(b']').is_some()) {
+                    return Some(());
+                }
+                for index in 0.. {
+                    self.value(format!("{}[{}]", path, index))?;
+                    if (self.consume(b',').is_none()) {
+                        break;
+                    }
+                }
+                self.consume(b']')
+            }
+            b'"' => self.string().map(|_| ()),
+            _ => {
+                while (self.i < self.s.len()
+                    && !matches!(self.s.as_bytes()[self.i], b',' | b'}' | b']')
+                    && !self.s.as_bytes()[self.i].is_ascii_whitespace())
+                {
+                    self.i += 1;
+                }
+                Some(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_strip {
+    //{{{
+    use super::*;
+
+    use serde_json::{json, Value};
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let input = r##"
+            # whole-line comment
+            {
+                "a": "# not a comment", # inline comment
+                // whole-line comment
+                "b": "// not a comment", // inline comment
+                /* block
+                   comment */
+                "c": "/* not a comment */",
+                "d": [1, 2, 3,],
+                "e": {"f": "\"#,",},
+                "g": 1 /* inline block comment */,
+            }
+        "##;
+        let expected = json!({
+            "a": "# not a comment",
+            "b": "// not a comment",
+            "c": "/* not a comment */",
+            "d": [1, 2, 3],
+            "e": {"f": "\"#,"},
+            "g": 1
+        });
+        let actual = strip(input);
+        println!("{:?}", actual);
+        assert!(actual.is_ok());
+        let actual = actual.unwrap();
+        assert_eq!(input.len(), actual.len());
+        assert_eq!(input.lines().count(), actual.lines().count());
+        assert_eq!(expected, serde_json::from_str::<Value>(&actual).unwrap());
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        //the error position points to the original source
+        let input = "{\n    /* コメント */ \"a\": 1,\n    \"b\": x\n}";
+        let actual = strip(input).unwrap();
+        let e = serde_json::from_str::<Value>(&actual).unwrap_err();
+        assert_eq!(3, e.line());
+        assert_eq!(10, e.column());
+    }
+
+    #[test]
+    // #[ignore]
+    fn test03() {
+        let input = "{\n    \"a\": 1 /* unterminated\n}";
+        let actual = strip(input);
+        assert_eq!(
+            "unterminated comment at line 2 column 12",
+            actual.map_err(|e| e.to_string()).unwrap_err()
+        );
+    }
+    //}}}
+}
+
+#[cfg(test)]
+mod tests_locate {
+    //{{{
+    use super::*;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let input = r#"{
+    "base_url": "http://example.com", # comment
+    "requests": [
+        {
+            "name": "a", "url": "/",
+            "headers": {"Content-Type": "application/json"},
+            "params": {"a": [1, {"b": null}]}
+        },
+        {}
+    ]
+}"#;
+        let m = locate(&strip(input).unwrap());
+        println!("{:?}", m);
+        assert_eq!(Some(&(1, 1)), m.get(""));
+        assert_eq!(Some(&(2, 17)), m.get("base_url"));
+        assert_eq!(Some(&(3, 17)), m.get("requests"));
+        assert_eq!(Some(&(4, 9)), m.get("requests[0]"));
+        assert_eq!(Some(&(5, 21)), m.get("requests[0].name"));
+        assert_eq!(Some(&(5, 33)), m.get("requests[0].url"));
+        assert_eq!(Some(&(6, 41)), m.get("requests[0].headers.Content-Type"));
+        assert_eq!(Some(&(7, 39)), m.get("requests[0].params.a[1].b"));
+        assert_eq!(Some(&(9, 9)), m.get("requests[1]"));
+        assert_eq!(None, m.get("requests[2]"));
+    }
+    //}}}
+}
@@ -1,19 +1,69 @@
-use std::error::Error;
+use std::{
+    io::{self, Write},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
 
 use bat::PrettyPrinter;
+use clap::ValueEnum;
 use itertools::Itertools;
-use reqwest::blocking::Response;
+use reqwest::{header::HeaderMap, StatusCode};
 use serde::Serialize;
-use serde_json::{Map, Value};
+use serde_json::{json, Map, Value};
+
+use error::Error;
 
 pub mod args;
+pub mod bench;
 pub mod client;
+pub mod compression;
 pub mod config;
+pub mod error;
+pub mod filter;
+pub mod graphql;
+pub mod har;
+pub mod history;
+pub mod jsonc;
+pub mod lint;
 pub mod logger;
+pub mod redact;
+pub mod redirect;
+pub mod render;
+pub mod stream;
+pub mod timing;
+pub mod websocket;
+
+//whether ANSI escape sequences are emitted
+static COLOR: AtomicBool = AtomicBool::new(true);
+
+//how a response is printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Output {
+    //the status line, the headers (optional) and the syntax-highlighted body
+    Pretty,
+    //the response body only
+    Raw,
+    //a JSON object with the status, the headers, the body and the timings
+    Json,
+}
+
+//enables or disables colours (e.g. disabled when stdout is not a TTY or `NO_COLOR` is set)
+pub fn set_color(enabled: bool) {
+    COLOR.store(enabled, Ordering::Relaxed);
+}
+
+//wraps `s` with the ANSI escape sequence `ESC[<code>m` if colours are enabled
+pub fn paint(code: &str, s: &str) -> String {
+    if (COLOR.load(Ordering::Relaxed)) {
+        format!("\u{001B}[{}m{}\u{001B}[0m", code, s)
+    } else {
+        s.to_owned()
+    }
+}
 
 //serializes `Value` with four-space indent
 //ref: |https://stackoverflow.com/a/49087292/8776746|
-fn to_string_pretty_four_space_indent(v: Value) -> String {
+pub(crate) fn to_string_pretty_four_space_indent(v: Value) -> String {
     let mut buf = Vec::new();
     let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
     let mut ser = serde_json::Serializer::with_formatter(&mut buf, formatter);
@@ -21,12 +71,21 @@ fn to_string_pretty_four_space_indent(v: Value) -> String {
     String::from_utf8(buf).unwrap()
 }
 
-fn bat(s: &str, language: Option<&str>) -> Result<(), Box<dyn Error>> {
+pub(crate) fn format_duration(d: Duration) -> String {
+    if (d < Duration::from_secs(1)) {
+        format!("{:.2}ms", d.as_secs_f64() * 1000.0)
+    } else {
+        format!("{:.3}s", d.as_secs_f64())
+    }
+}
+
+fn bat(s: &str, language: Option<&str>) -> Result<(), Error> {
     let mut printer = PrettyPrinter::new();
     printer
         .input_from_bytes(s.trim().as_bytes())
         .tab_width(Some(4))
-        .true_color(false);
+        .true_color(false)
+        .colored_output(COLOR.load(Ordering::Relaxed));
     if let Some(lang) = language {
         printer.language(lang);
     }
@@ -35,82 +94,707 @@ fn bat(s: &str, language: Option<&str>) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-pub fn show_config(config: &config::Config) -> Result<(), Box<dyn Error>> {
-    let config = serde_json::to_value(config)?;
-    let s = to_string_pretty_four_space_indent(config);
+//The secrets are hidden as in the logs.
+pub fn show_config(config: &config::Config) -> Result<(), Error> {
+    let mut value = serde_json::to_value(config)?;
+    redact::Redactor::new(config).json(&mut value);
+    let s = to_string_pretty_four_space_indent(value);
     bat(&s, Some("json"))
 }
 
 pub fn show_complete(config: &config::Config) {
     let request_names = config.requests.iter().map(|e| &e.name).join(" ");
-    let cli_options = "-f --file --show-headers --disable-redirect --complete -v --verbose";
+    let cli_options = "lint validate schema bench introspect history replay --name --status --limit -n --requests -c --concurrency -d --duration -f --file -g --group -j --jobs --order --output -o --output-file --filter --show-headers --timing --http --compressed --disable-redirect --max-redirects --same-host-redirects --export-har --import-har --complete -v --verbose";
     let words = format!("{} {}", request_names, cli_options);
 
     let command = format!(
-        "complete -f -W '{}' -X '!@({}|*.json)' ycurl",
+        "complete -f -W '{}' -X '!@({}|*.json|*.yaml|*.yml|*.toml)' ycurl",
         words,
         words.replace(' ', "|")
     );
     println!("{}", command);
 }
 
-pub fn show_requests(config: &config::Config) -> Result<(), Box<dyn Error>> {
+pub fn show_requests(config: &config::Config) -> Result<(), Error> {
     let mut l = vec![];
     for i in 0..config.requests.len() {
         if (config.requests[i].disabled) {
             continue;
         }
-        l.push(format!(
-            r#"{{"index": {}, "name": "{}", "url": "{}"}}"#,
-            i, config.requests[i].name, config.requests[i].url
-        ));
+        if let Some(group) = &config.requests[i].group {
+            l.push(format!(
+                r#"{{"index": {}, "name": "{}", "url": "{}", "group": "{}"}}"#,
+                i, config.requests[i].name, config.requests[i].url, group
+            ));
+        } else {
+            l.push(format!(
+                r#"{{"index": {}, "name": "{}", "url": "{}"}}"#,
+                i, config.requests[i].name, config.requests[i].url
+            ));
+        }
     }
     let s = l.iter().join("\n");
     bat(&s, Some("json"))?;
     Ok(())
 }
 
+pub fn show_schema() {
+    println!("{}", to_string_pretty_four_space_indent(config::schema()));
+}
+
+//prints the requests converted from a HAR file so that they can be pasted into `requests`
+pub fn show_har_import(har_file: &str) -> Result<(), Error> {
+    let requests = serde_json::to_value(har::import(har_file)?)?;
+    println!("{}", to_string_pretty_four_space_indent(requests));
+    Ok(())
+}
+
+//prints the URL of the request with the query parameters (used in verbose mode)
+pub fn show_url(request: &config::Request, redactor: &redact::Redactor) {
+    if (request.params.is_empty()) {
+        println!("{}\n", redactor.url(&request.url));
+    } else {
+        let query_parameters = request
+            .params
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v.to_string().trim_matches('"')))
+            .join("&");
+        println!(
+            "{}\n",
+            redactor.url(&format!("{}?{}", request.url, query_parameters))
+        );
+    }
+}
+
+//`req` is the request built by `client::Client::request()`
+pub fn log_request(
+    logger: &mut logger::Logger,
+    req: Option<&reqwest::Request>,
+    request: &config::Request,
+) -> Result<(), Error> {
+    let redactor = logger.redactor().clone();
+    logger.log("[request]\n")?;
+    if let Some(req) = req {
+        logger.log(&format!("method: {}\n", req.method()))?;
+        logger.log(&format!("url: {}\n", redactor.url(req.url().as_str())))?;
+        logger.log(&format!("headers: {:?}\n", redactor.headers(req.headers())))?;
+    } else {
+        let mut request = serde_json::to_value(request)?;
+        redactor.json(&mut request);
+        logger.log(&format!("request: {}\n", request))?;
+    }
+    if let Some(body) = &request.body {
+        let mut body = body.clone();
+        redactor.json(&mut body);
+        logger.log(&format!("body: {:?}", body))
+    } else {
+        logger.log("body: None")
+    }
+}
+
+//prints the timing breakdown and the metadata of the response
+fn show_timing(response: &client::Response) {
+    let timings = &response.timings;
+    let optional = |d: Option<Duration>| d.map(format_duration).unwrap_or("-".to_owned());
+    //the TLS handshake cannot be timed on its own (see `timing::Recorded`), which is shown rather than omitted
+    let (connect, tls) = if (response.url.scheme() == "https") {
+        (
+            format!("{} (TCP + TLS)", optional(timings.connect)),
+            "(included in connect)".to_owned(),
+        )
+    } else {
+        (optional(timings.connect), "-".to_owned())
+    };
+    let size = match (&response.encoded) {
+        Some(e) => format!(
+            "{} bytes ({}: {} bytes)",
+            response.size(),
+            e.encoding.as_str(),
+            e.size
+        ),
+        None => format!("{} bytes", response.size()),
+    };
+    let lines = [
+        format!("{:?}, {}", response.version, size),
+        format!("dns       {}", optional(timings.dns)),
+        format!("connect   {}", connect),
+        format!("tls       {}", tls),
+        format!("ttfb      {}", format_duration(timings.wait)),
+        format!("download  {}", format_duration(timings.receive)),
+        format!("total     {}", format_duration(timings.total())),
+    ];
+    println!("{}", paint("090", &lines.join("\n")));
+    //caveats on the figures above
+    if (!response.redirects.is_empty()) {
+        println!(
+            "{}",
+            paint(
+                "033",
+                &format!(
+                    "({} redirect(s) followed: `dns` and `connect` are summed over the connections made, and `ttfb` includes all of the hops)",
+                    response.redirects.len()
+                )
+            )
+        );
+    }
+    if (timings.concurrent) {
+        println!(
+            "{}",
+            paint(
+                "033",
+                "(sent concurrently with other requests: the durations may include the time spent on them)"
+            )
+        );
+    }
+}
+
+pub(crate) fn headers_to_map(headers: &HeaderMap) -> Map<String, Value> {
+    let mut m = Map::new();
+    for (k, v) in headers {
+        m.insert(
+            k.to_string(),
+            Value::String(String::from_utf8_lossy(v.as_bytes()).into_owned()),
+        );
+    }
+    m
+}
+
+fn saved_note(saved: &client::Saved) -> String {
+    format!("(saved to {} ({} bytes))", saved.path.display(), saved.size)
+}
+
+fn log_response(logger: &mut logger::Logger, response: &client::Response) -> Result<(), Error> {
+    let redactor = logger.redactor().clone();
+    for r in &response.redirects {
+        logger.log(&format!(
+            "\n[redirect] {}: {} -> {} {}",
+            r.status,
+            redactor.url(r.from.as_str()),
+            r.method,
+            redactor.url(r.to.as_str())
+        ))?;
+    }
+    logger.log("\n[response]\n")?;
+    logger.log(&format!("{}", response.status))?;
+    logger.log(&format!("\n{:?}", redactor.headers(&response.headers)))?;
+
+    if let Some(saved) = &response.saved {
+        return logger.log(&format!("\n{}", saved_note(saved)));
+    }
+    if (response.text().trim().is_empty()) {
+        return Ok(());
+    }
+    logger.log(&format!("\n{}", redactor.body(&response.render().text)))
+}
+
+//An NDJSON body is filtered record by record.
+fn apply_filter(filter: &filter::Filter, response: &client::Response) -> Result<Vec<Value>, Error> {
+    let not_json = || filter.error("the response body is not JSON");
+    let text = response.text();
+    let records = if (response.kind() == render::Kind::Ndjson) {
+        text.lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| serde_json::from_str::<Value>(l).map_err(|_| not_json()))
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        vec![serde_json::from_str::<Value>(&text).map_err(|_| not_json())?]
+    };
+    let mut ret = vec![];
+    for v in records {
+        ret.extend(filter.apply(v)?);
+    }
+    Ok(ret)
+}
+
+//prints each redirect followed as `302 Found: <from> -> GET <to>` (used in verbose mode)
+fn show_redirects(redirects: &[redirect::Redirect], redactor: &redact::Redactor) {
+    for r in redirects {
+        let line = format!(
+            "{}: {} -> {} {}",
+            r.status,
+            redactor.url(r.from.as_str()),
+            r.method,
+            redactor.url(r.to.as_str())
+        );
+        println!("{}", paint("090", &line));
+    }
+}
+
+fn show_status(status: StatusCode) {
+    if (status.is_success()) {
+        println!("{}", paint("032", &status.to_string()));
+    } else {
+        println!("{}", paint("031", &status.to_string()));
+    }
+}
+
+fn show_headers(headers: &HeaderMap) -> Result<(), Error> {
+    let s = serde_json::to_string(&headers_to_map(headers))?;
+    println!();
+    bat(&s, Some("json"))
+}
+
+fn stdout_error(e: io::Error) -> Error {
+    Error::Other(format!("failed to write to stdout: {}", e))
+}
+
+//writes `v` in one line, where a string is written without quotes (like `jq -r`)
+fn write_raw_value(w: &mut impl Write, v: &Value) -> Result<(), Error> {
+    match (v) {
+        Value::String(s) => writeln!(w, "{}", s),
+        v => writeln!(w, "{}", v),
+    }
+    .map_err(stdout_error)
+}
+
+//prints the operations listed by `ycurl introspect`, grouped by the kind
+pub fn show_operations(operations: &[graphql::Operation]) {
+    for operations in operations.chunk_by(|a, b| a.kind == b.kind) {
+        println!("{}", paint("1", operations[0].kind));
+        for o in operations {
+            println!("    {}", o);
+            if let Some(description) = &o.description {
+                println!("{}", paint("090", &format!("        {}", description)));
+            }
+        }
+    }
+}
+
+//prints the `errors` of a GraphQL response in red
+fn show_graphql_errors(errors: &[String]) {
+    let mut lines = vec![format!("errors ({}):", errors.len())];
+    lines.extend(errors.iter().map(|e| format!("- {}", e)));
+    println!("\n{}", paint("031", &lines.join("\n")));
+}
+
+//`filter` is applied to the body before it is printed, while the log records the body as it is
+//The `errors` of a GraphQL response are printed separately from the rest of the body.
 pub fn pretty_print(
-    res: Response,
+    response: &client::Response,
     logger: &mut logger::Logger,
     config: &config::Config,
-) -> Result<(), Box<dyn Error>> {
-    if (res.status().is_success()) {
-        println!("\u{001B}[032m{}\u{001B}[0m", res.status());
-    } else {
-        println!("\u{001B}[031m{}\u{001B}[0m", res.status());
+    request: &config::Request,
+    filter: Option<&filter::Filter>,
+) -> Result<(), Error> {
+    log_response(logger, response)?;
+
+    if (config.cli_options.verbose) {
+        show_redirects(&response.redirects, logger.redactor());
+    }
+    show_status(response.status);
+    if (config.cli_options.show_timing) {
+        show_timing(response);
     }
     if (config.cli_options.show_headers) {
-        let mut m = Map::new();
-        for (k, v) in res.headers() {
-            m.insert(k.to_string(), Value::String(v.to_str()?.to_owned()));
+        show_headers(&response.headers)?;
+    }
+
+    if let Some(saved) = &response.saved {
+        println!("\n{}", paint("090", &saved_note(saved)));
+        return Ok(());
+    }
+
+    if (response.text().trim().is_empty()) {
+        return Ok(());
+    }
+
+    let graphql = if (request.graphql.is_some()) {
+        response.json::<Value>().ok().and_then(|body| {
+            let errors = graphql::errors(&body)?;
+            Some((body, errors))
+        })
+    } else {
+        None
+    };
+
+    if let Some(filter) = filter {
+        let body = apply_filter(filter, response)?
+            .into_iter()
+            .map(to_string_pretty_four_space_indent)
+            .join("\n");
+        println!();
+        bat(&body, Some("json"))?;
+        if let Some((_, errors)) = graphql {
+            show_graphql_errors(&errors);
         }
-        let s = serde_json::to_string(&Value::from(m))?;
+        return Ok(());
+    }
+
+    if let Some((mut body, errors)) = graphql {
+        body.as_object_mut().unwrap().remove("errors");
         println!();
-        bat(&s, Some("json"))?;
+        bat(&to_string_pretty_four_space_indent(body), Some("json"))?;
+        show_graphql_errors(&errors);
+        return Ok(());
     }
 
-    logger.log("\n[response]\n")?;
-    logger.log(&format!("{}", res.status()))?;
-    logger.log(&format!("\n{:?}", res.headers()))?;
+    let rendered = response.render();
+    println!();
+    bat(&rendered.text, rendered.language)?;
+    if (rendered.kind == render::Kind::Binary) {
+        println!(
+            "{}",
+            paint(
+                "090",
+                &format!(
+                    "(binary data ({} bytes); use `--output-file` or `save_to` to save it)",
+                    response.body.len()
+                )
+            )
+        );
+    }
+
+    Ok(())
+}
 
-    let mut body = res.text()?;
-    if (body.trim().is_empty()) {
+//prints the response body as it is (nothing if it is saved to a file)
+//When `filter` is given, its outputs are printed one per line, where strings are printed without quotes (like `jq -r`).
+pub fn print_raw(
+    response: &client::Response,
+    logger: &mut logger::Logger,
+    filter: Option<&filter::Filter>,
+) -> Result<(), Error> {
+    log_response(logger, response)?;
+    if (response.saved.is_some()) {
         return Ok(());
     }
 
-    let mut language = None;
-    if let Ok(v) = serde_json::from_str::<Value>(&body) {
-        body = to_string_pretty_four_space_indent(v);
-        language = Some("json");
-    } else if (body.starts_with('<')) {
-        language = Some("html");
+    let mut stdout = io::stdout().lock();
+    match (filter) {
+        None => stdout.write_all(&response.body).map_err(stdout_error)?,
+        Some(filter) => {
+            for v in apply_filter(filter, response)? {
+                write_raw_value(&mut stdout, &v)?;
+            }
+        }
     }
+    stdout.flush().map_err(stdout_error)
+}
 
-    println!();
-    bat(&body, language)?;
+//prints the response as a JSON object in one line
+//`body` is embedded as JSON if it is valid JSON, as a string otherwise, and as `null` if it is saved to a file. When `filter` is given, `body` is the output of the filter (an array if it outputs zero or two or more values).
+pub fn print_json(
+    name: &str,
+    response: &client::Response,
+    logger: &mut logger::Logger,
+    filter: Option<&filter::Filter>,
+) -> Result<(), Error> {
+    log_response(logger, response)?;
 
-    logger.log(&format!("\n{}", body))?;
+    let body = match (filter) {
+        _ if (response.saved.is_some()) => Value::Null,
+        Some(filter) => {
+            let mut l = apply_filter(filter, response)?;
+            if (l.len() == 1) {
+                l.remove(0)
+            } else {
+                Value::Array(l)
+            }
+        }
+        None => serde_json::from_slice(&response.body)
+            .unwrap_or_else(|_| Value::String(response.text())),
+    };
+    println!("{}", response_to_json(name, response, body));
+    Ok(())
+}
 
+fn response_to_json(name: &str, response: &client::Response, body: Value) -> Value {
+    //milliseconds with microsecond precision
+    let ms = |d: Duration| d.as_micros() as f64 / 1000.0;
+    let timings = &response.timings;
+    json!({
+        "name": name,
+        "url": response.url.as_str(),
+        "status": response.status.as_u16(),
+        "version": format!("{:?}", response.version),
+        "headers": headers_to_map(&response.headers),
+        "body": body,
+        "size": response.size(),
+        "saved_to": response.saved.as_ref().map(|s| s.path.display().to_string()),
+        "encoding": response.encoded.map(|e| e.encoding.as_str()),
+        "encoded_size": response.encoded.map(|e| e.size),
+        "timings": {
+            "dns": timings.dns.map(ms),
+            "connect": timings.connect.map(ms),
+            "ttfb": ms(timings.wait),
+            "download": ms(timings.receive),
+            "total": ms(timings.total()),
+        },
+    })
+}
+
+fn show_sse_event(event: &Map<String, Value>) -> Result<(), Error> {
+    let mut header = format!("event: {}", event["event"].as_str().unwrap_or_default());
+    if let Some(id) = event.get("id").and_then(|id| id.as_str()) {
+        header.push_str(&format!(", id: {}", id));
+    }
+    println!("{}", paint("090", &header));
+    match (&event["data"]) {
+        Value::String(s) => bat(s, None),
+        v => bat(&to_string_pretty_four_space_indent(v.clone()), Some("json")),
+    }
+}
+
+//prints the events of a streaming response (Server-Sent Events or NDJSON) one by one as they arrive, and returns the response with the summary of the events
+//`filter` is applied to each event. In the `json` format, the events are collected into `body` of the object printed at the end.
+pub async fn print_stream(
+    head: client::Head,
+    format: stream::Format,
+    logger: &mut logger::Logger,
+    config: &config::Config,
+    request: &config::Request,
+    filter: Option<&filter::Filter>,
+    output: Output,
+) -> Result<(client::Response, stream::Summary), Error> {
+    let options = stream::Options::new(request.stream.as_ref())?;
+    //whether an SSE event is printed as it is (i.e. not transformed by `filter`)
+    let is_sse = (format == stream::Format::Sse && filter.is_none());
+
+    if (output == Output::Pretty) {
+        if (config.cli_options.verbose) {
+            show_redirects(&head.response().redirects, logger.redactor());
+        }
+        show_status(head.response().status);
+        if (config.cli_options.show_headers) {
+            show_headers(&head.response().headers)?;
+        }
+    }
+
+    let mut events = vec![];
+    let (response, summary) = stream::read(head, format, &options, |v| {
+        let values = match (filter) {
+            Some(filter) => filter.apply(v.clone())?,
+            None => vec![v.clone()],
+        };
+        match (output) {
+            Output::Pretty => {
+                for v in values {
+                    println!();
+                    match (v) {
+                        Value::Object(m) if (is_sse) => show_sse_event(&m)?,
+                        v => bat(&to_string_pretty_four_space_indent(v), Some("json"))?,
+                    }
+                }
+            }
+            Output::Raw => {
+                let mut stdout = io::stdout().lock();
+                for v in values {
+                    match (v) {
+                        Value::Object(m) if (is_sse) => write_raw_value(&mut stdout, &m["data"])?,
+                        v => write_raw_value(&mut stdout, &v)?,
+                    }
+                }
+                stdout.flush().map_err(stdout_error)?;
+            }
+            Output::Json => events.extend(values),
+        }
+        Ok(())
+    })
+    .await?;
+    log_response(logger, &response)?;
+
+    match (output) {
+        Output::Pretty => {
+            if (config.cli_options.show_timing) {
+                println!();
+                show_timing(&response);
+            }
+            let mut lines = vec![format!(
+                "({} events{})",
+                summary.events,
+                if (summary.stopped) { ", stopped" } else { "" }
+            )];
+            lines.extend(
+                summary
+                    .captured
+                    .iter()
+                    .map(|(k, v)| format!("{} = {}", k, v)),
+            );
+            println!("\n{}", paint("090", &lines.join("\n")));
+        }
+        Output::Raw => (),
+        Output::Json => {
+            let mut v = response_to_json(&request.name, &response, Value::Array(events));
+            v["events"] = json!(summary.events);
+            v["stopped"] = json!(summary.stopped);
+            v["captured"] = json!(summary.captured);
+            println!("{}", v);
+        }
+    }
+    Ok((response, summary))
+}
+
+//`filter` is applied to the received messages, while the log records them as they are
+//`record` is written to the structured log with the transcript, or with the error if the connection fails halfway
+pub async fn print_websocket(
+    connection: websocket::Connection,
+    logger: &mut logger::Logger,
+    record: &logger::Record,
+    config: &config::Config,
+    request: &config::Request,
+    filter: Option<&filter::Filter>,
+    output: Output,
+) -> Result<(), Error> {
+    use websocket::{Data, Direction};
+
+    if (output == Output::Pretty) {
+        show_status(connection.status);
+        if (config.cli_options.show_headers) {
+            show_headers(&connection.headers)?;
+        }
+    }
+
+    let mut messages = vec![];
+    let transcript = connection
+        .run(|frame| {
+            let (arrow, direction) = match (frame.direction) {
+                Direction::Sent => (">", "sent"),
+                Direction::Received => ("<", "received"),
+            };
+            let values = match (frame.data.value()) {
+                Some(v) if (frame.direction == Direction::Received) => match (filter) {
+                    Some(filter) => Some(filter.apply(v)?),
+                    None => Some(vec![v]),
+                },
+                v => v.map(|v| vec![v]),
+            };
+            match (output) {
+                Output::Pretty => {
+                    println!();
+                    match (&frame.data) {
+                        Data::Binary(b) => {
+                            println!(
+                                "{}",
+                                paint(
+                                    "090",
+                                    &format!("{} {} ({} bytes)", arrow, direction, b.len())
+                                )
+                            );
+                            let body = render::render(Some(&mime::APPLICATION_OCTET_STREAM), b);
+                            bat(&body.text, None)?;
+                        }
+                        Data::Text(_) => {
+                            println!("{}", paint("090", &format!("{} {}", arrow, direction)));
+                            for v in values.unwrap_or_default() {
+                                match (v) {
+                                    Value::String(s) => bat(&s, None)?,
+                                    v => bat(&to_string_pretty_four_space_indent(v), Some("json"))?,
+                                }
+                            }
+                        }
+                    }
+                }
+                Output::Raw if (frame.direction == Direction::Received) => {
+                    let mut stdout = io::stdout().lock();
+                    match (&frame.data) {
+                        Data::Binary(b) => stdout.write_all(b).map_err(stdout_error)?,
+                        Data::Text(_) => {
+                            for v in values.unwrap_or_default() {
+                                write_raw_value(&mut stdout, &v)?;
+                            }
+                        }
+                    }
+                    stdout.flush().map_err(stdout_error)?;
+                }
+                Output::Raw => (),
+                Output::Json => match (values) {
+                    Some(values) => messages.extend(
+                        values
+                            .into_iter()
+                            .map(|v| json!({"direction": direction, "data": v})),
+                    ),
+                    None => messages.push(
+                        json!({"direction": direction, "data": null, "size": frame.data.len()}),
+                    ),
+                },
+            }
+            Ok(())
+        })
+        .await;
+    let transcript = match (transcript) {
+        Ok(t) => t,
+        Err(e) => {
+            let _ = logger.record(&record.clone().with_error(&e));
+            return Err(e);
+        }
+    };
+    let redactor = logger.redactor().clone();
+    logger.record(&record.clone().with_transcript(&transcript, &redactor))?;
+
+    logger.log("\n[websocket]\n")?;
+    logger.log(&format!("{}", transcript.status))?;
+    logger.log(&format!("\n{:?}\n", redactor.headers(&transcript.headers)))?;
+    for frame in &transcript.frames {
+        logger.log(&logger::frame_line(frame, &redactor))?;
+    }
+    logger.log(&format!("(end: {})", transcript.end.as_str()))?;
+
+    let end = match (transcript.end) {
+        websocket::End::Closed => "closed by the server",
+        websocket::End::Timeout => "timed out",
+        websocket::End::MaxMessages => "reached `max_messages`",
+    };
+    match (output) {
+        Output::Pretty => {
+            println!();
+            if (config.cli_options.show_timing) {
+                let lines = [
+                    format!("handshake  {}", format_duration(transcript.handshake)),
+                    format!("total      {}", format_duration(transcript.total)),
+                ];
+                println!("{}\n", paint("090", &lines.join("\n")));
+            }
+            println!(
+                "{}",
+                paint(
+                    "090",
+                    &format!(
+                        "({} sent, {} received, {})",
+                        transcript.frames.len() - transcript.received(),
+                        transcript.received(),
+                        end
+                    )
+                )
+            );
+            for (expr, satisfied) in &transcript.expectations {
+                if (*satisfied) {
+                    println!("{}", paint("032", &format!("✓ {}", expr)));
+                } else {
+                    println!("{}", paint("031", &format!("✗ {}", expr)));
+                }
+            }
+        }
+        Output::Raw => (),
+        Output::Json => {
+            //milliseconds with microsecond precision
+            let ms = |d: Duration| d.as_micros() as f64 / 1000.0;
+            let v = json!({
+                "name": request.name,
+                "url": transcript.url.as_str(),
+                "status": transcript.status.as_u16(),
+                "headers": headers_to_map(&transcript.headers),
+                "messages": messages,
+                "end": transcript.end.as_str(),
+                "expectations": transcript
+                    .expectations
+                    .iter()
+                    .map(|(expr, satisfied)| json!({"expr": expr, "satisfied": satisfied}))
+                    .collect::<Vec<_>>(),
+                "timings": {
+                    "handshake": ms(transcript.handshake),
+                    "total": ms(transcript.total),
+                },
+            });
+            println!("{}", v);
+        }
+    }
+
+    let unmet = transcript.unmet();
+    if (!unmet.is_empty()) {
+        return Err(Error::Expectation {
+            request: request.name.clone(),
+            unmet,
+        });
+    }
     Ok(())
 }
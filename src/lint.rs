@@ -0,0 +1,903 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use clap::CommandFactory;
+use indexmap::IndexMap;
+use regex::Regex;
+use reqwest::{
+    header::{HeaderName, HeaderValue},
+    Url,
+};
+use serde_json::Value;
+
+use super::args;
+use super::client;
+use super::config::{self, Config, Format, Message, RawConfig, Request};
+use super::error::Error;
+use super::filter::Filter;
+use super::jsonc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    //path in the merged config like `requests[0].headers.Accept` (empty for the whole config)
+    pub path: String,
+    pub message: String,
+}
+
+const TYPE_CAST_PREFIXES: [&str; 2] = ["number:", "bool:"];
+
+struct Linter {
+    regex: Regex,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Linter {
+    fn error(&mut self, path: &str, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            path: path.to_owned(),
+            message,
+        });
+    }
+
+    fn warning(&mut self, path: &str, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Warning,
+            path: path.to_owned(),
+            message,
+        });
+    }
+
+    fn references<'a>(&self, s: &'a str) -> Vec<&'a str> {
+        self.regex
+            .captures_iter(s)
+            .map(|c| c.get(1).unwrap().as_str())
+            .collect()
+    }
+
+    //unlike `config::variable_expansion()`, undefined variables are left as they are
+    fn expand(&self, s: &str, variables: &IndexMap<String, String>) -> String {
+        self.regex
+            .replace_all(s, |c: &regex::Captures| {
+                variables
+                    .get(&c[1])
+                    .cloned()
+                    .unwrap_or_else(|| c[0].to_owned())
+            })
+            .into_owned()
+    }
+
+    //reports undefined variables in `s` and records the used ones
+    //`local_variables` are the names of the local-to-request variables defined so far, which shadow the global ones.
+    fn check_references(
+        &mut self,
+        path: &str,
+        s: &str,
+        variables: &IndexMap<String, String>,
+        local_variables: &HashSet<String>,
+        global_used: &mut HashSet<String>,
+        local_used: &mut HashSet<String>,
+    ) {
+        for name in self.references(s) {
+            if (local_variables.contains(name)) {
+                local_used.insert(name.to_owned());
+            } else if (variables.contains_key(name)) {
+                global_used.insert(name.to_owned());
+            } else {
+                self.error(path, format!("variable `{}` is not defined", name));
+            }
+        }
+    }
+
+    //reports the error of a check shared with `Config::new()`
+    fn check_config(&mut self, result: Result<(), Error>) {
+        if let Err(Error::InvalidConfig { message, path, .. }) = result {
+            self.error(&path.unwrap_or_default(), message);
+        }
+    }
+
+    fn check_header(&mut self, path: &str, name: &str, value: &str) {
+        if (HeaderName::from_bytes(name.as_bytes()).is_err()) {
+            self.error(path, format!("invalid header name: `{}`", name));
+        }
+        if (HeaderValue::from_str(value).is_err()) {
+            self.error(path, format!("invalid header value: `{}`", value));
+        }
+    }
+
+    fn check_unreachable_type_cast(&mut self, path: &str, s: &str) {
+        if let Some(prefix) = TYPE_CAST_PREFIXES.iter().find(|p| s.starts_with(*p)) {
+            self.warning(
+                path,
+                format!(
+                    "type cast is performed only inside a map-typed `body`, so `{}` is sent as it is",
+                    prefix
+                ),
+            );
+        }
+    }
+
+    fn check_type_cast(&mut self, path: &str, s: &str) {
+        if let Some(n) = s.strip_prefix("number:") {
+            if (n.parse::<serde_json::Number>().is_err()) {
+                self.error(path, format!("`{}` cannot be cast to a number", n));
+            }
+        } else if let Some(b) = s.strip_prefix("bool:") {
+            if (b.parse::<bool>().is_err()) {
+                self.error(path, format!("`{}` cannot be cast to a bool", b));
+            }
+        }
+    }
+
+    fn lint_request(
+        &mut self,
+        i: usize,
+        request: &Request,
+        config: &Config,
+        global_variables: &IndexMap<String, String>,
+        global_used: &mut HashSet<String>,
+    ) {
+        let prefix = format!("requests[{}]", i);
+
+        let mut variables = global_variables.clone();
+        let mut local_variables = HashSet::new();
+        let mut local_used = HashSet::new();
+        for (k, v) in request.variables.iter().flatten() {
+            self.check_references(
+                &format!("{}.variables.{}", prefix, k),
+                v,
+                &variables,
+                &local_variables,
+                global_used,
+                &mut local_used,
+            );
+            variables.insert(k.clone(), self.expand(v, &variables));
+            local_variables.insert(k.clone());
+        }
+
+        let mut strings = vec![(format!("{}.url", prefix), request.url.clone())];
+        for (k, v) in &request.headers {
+            strings.push((format!("{}.headers.{}", prefix, k), k.clone()));
+            strings.push((format!("{}.headers.{}", prefix, k), v.clone()));
+        }
+        for (k, v) in &request.params {
+            collect_strings(v, format!("{}.params.{}", prefix, k), &mut strings);
+        }
+        if let Some(body) = &request.body {
+            collect_strings(body, format!("{}.body", prefix), &mut strings);
+        }
+        if let Some(save_to) = &request.save_to {
+            strings.push((format!("{}.save_to", prefix), save_to.clone()));
+        }
+        if let Some(graphql) = &request.graphql {
+            if let Some(query) = &graphql.query {
+                strings.push((format!("{}.graphql.query", prefix), query.clone()));
+            }
+            for (k, v) in graphql.variables.iter().flatten() {
+                collect_strings(
+                    v,
+                    format!("{}.graphql.variables.{}", prefix, k),
+                    &mut strings,
+                );
+            }
+            if let Some(name) = &graphql.operation_name {
+                strings.push((format!("{}.graphql.operationName", prefix), name.clone()));
+            }
+        }
+        for (j, m) in request
+            .websocket
+            .iter()
+            .flat_map(|w| w.messages.iter())
+            .enumerate()
+        {
+            let path = format!("{}.websocket.messages[{}]", prefix, j);
+            match (m) {
+                Message::Text(text) => strings.push((format!("{}.text", path), text.clone())),
+                Message::Json(v) => collect_strings(v, format!("{}.json", path), &mut strings),
+                Message::File(file) => strings.push((format!("{}.file", path), file.clone())),
+            }
+        }
+        for (path, s) in &strings {
+            self.check_references(
+                path,
+                s,
+                &variables,
+                &local_variables,
+                global_used,
+                &mut local_used,
+            );
+        }
+
+        //URL
+        let url = self.expand(&request.url, &variables);
+        let url = if (client::is_absolute_url(&url)) {
+            url
+        } else {
+            format!("{}{}", config.base_url, url)
+        };
+        if let Err(e) = Url::parse(&url) {
+            self.error(
+                &format!("{}.url", prefix),
+                format!("malformed URL `{}`: {}", url, e),
+            );
+        }
+        self.check_unreachable_type_cast(&format!("{}.url", prefix), &request.url);
+
+        //headers
+        for (k, v) in &request.headers {
+            let path = format!("{}.headers.{}", prefix, k);
+            let (k, v) = (self.expand(k, &variables), self.expand(v, &variables));
+            self.check_header(&path, &k, &v);
+            self.check_unreachable_type_cast(&path, &v);
+        }
+
+        //query parameters
+        let mut params = vec![];
+        for (k, v) in &request.params {
+            collect_strings(v, format!("{}.params.{}", prefix, k), &mut params);
+        }
+        for (path, s) in params {
+            self.check_unreachable_type_cast(&path, &self.expand(&s, &variables));
+        }
+
+        //body
+        let body_path = format!("{}.body", prefix);
+        match (&request.body) {
+            None => (),
+            Some(Value::String(s)) => {
+                self.check_unreachable_type_cast(&body_path, &self.expand(s, &variables))
+            }
+            Some(Value::Object(o)) => {
+                let mut l = vec![];
+                for (k, v) in o {
+                    collect_strings(v, format!("{}.{}", body_path, k), &mut l);
+                }
+                for (path, s) in l {
+                    self.check_type_cast(&path, &self.expand(&s, &variables));
+                }
+            }
+            Some(v) => self.error(&body_path, format!("unsupported `body` type: {}", v)),
+        }
+
+        //GraphQL
+        if let Some(graphql) = &request.graphql {
+            let mut l = vec![];
+            for (k, v) in graphql.variables.iter().flatten() {
+                collect_strings(v, format!("{}.graphql.variables.{}", prefix, k), &mut l);
+            }
+            for (path, s) in l {
+                self.check_type_cast(&path, &self.expand(&s, &variables));
+            }
+        }
+        self.check_config(request.validate_graphql(i));
+        self.check_config(request.validate_websocket(i));
+
+        //`Content-Type` vs `body`
+        let content_type = request
+            .headers
+            .iter()
+            .chain(config.default_headers.iter())
+            .find(|(k, _)| k.eq_ignore_ascii_case("content-type"))
+            .map(|(_, v)| self.expand(v, &variables).to_lowercase());
+        match (&request.body, content_type) {
+            (Some(Value::Object(_)), None) => self.warning(
+                &body_path,
+                "map-typed `body` is sent as JSON but `Content-Type` is not set".to_owned(),
+            ),
+            (Some(Value::Object(_)), Some(ct))
+                if (!ct.contains("json") && !ct.contains("application/x-www-form-urlencoded")) =>
+            {
+                self.warning(
+                    &body_path,
+                    format!(
+                        "map-typed `body` is sent as JSON but `Content-Type` is `{}`",
+                        ct
+                    ),
+                )
+            }
+            (Some(Value::String(s)), Some(ct))
+                if (ct.contains("json")
+                    && serde_json::from_str::<Value>(&self.expand(s, &variables)).is_err()) =>
+            {
+                self.error(
+                    &body_path,
+                    format!("`Content-Type` is `{}` but `body` is not a valid JSON", ct),
+                )
+            }
+            (Some(Value::String(s)), Some(ct))
+                if (ct.contains("application/x-www-form-urlencoded")
+                    && s.trim_start().starts_with(['{', '['])) =>
+            {
+                self.warning(
+                    &body_path,
+                    format!("`Content-Type` is `{}` but `body` looks like JSON", ct),
+                )
+            }
+            _ => (),
+        }
+
+        for (expr, path) in request.filters() {
+            if let Err(Error::Filter { message, .. }) = Filter::new(expr) {
+                self.error(
+                    &format!("{}.{}", prefix, path),
+                    format!("invalid expression `{}`: {}", expr, message),
+                );
+            }
+        }
+
+        if (request.stream.as_ref().and_then(|s| s.max_events) == Some(0)) {
+            self.error(
+                &format!("{}.stream.max_events", prefix),
+                "should be greater than 0".to_owned(),
+            );
+        }
+
+        for k in request.variables.iter().flat_map(|m| m.keys()) {
+            if (!local_used.contains(k)) {
+                self.warning(
+                    &format!("{}.variables.{}", prefix, k),
+                    format!("variable `{}` is never used", k),
+                );
+            }
+        }
+    }
+}
+
+//collects the strings in `v` with their paths
+fn collect_strings(v: &Value, path: String, out: &mut Vec<(String, String)>) {
+    match (v) {
+        Value::String(s) => out.push((path, s.clone())),
+        Value::Array(l) => {
+            for (i, e) in l.iter().enumerate() {
+                collect_strings(e, format!("{}[{}]", path, i), out);
+            }
+        }
+        Value::Object(o) => {
+            for (k, e) in o {
+                collect_strings(e, format!("{}.{}", path, k), out);
+            }
+        }
+        _ => (),
+    }
+}
+
+//checks the config before variable expansion and reports all of the problems found
+pub fn lint(raw: &RawConfig) -> Result<Vec<Diagnostic>, Error> {
+    let mut linter = Linter {
+        regex: Regex::new(r#"\$\{([^}]+)}"#)?,
+        diagnostics: vec![],
+    };
+
+    let mut value = raw.value.clone();
+    config::take_secret_variables(&mut value);
+    //The requests are deserialized one by one so that a malformed request does not hide the problems in the others.
+    let requests = match (value.get_mut("requests")) {
+        Some(Value::Array(l)) => std::mem::take(l),
+        _ => vec![],
+    };
+    let config = match (serde_json::from_value::<Config>(value)) {
+        Ok(c) => Some(c),
+        Err(e) => {
+            linter.error("", e.to_string());
+            None
+        }
+    };
+    let requests = requests
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, r)| match (serde_json::from_value::<Request>(r)) {
+            Ok(r) => Some((i, r)),
+            Err(e) => {
+                linter.error(&format!("requests[{}]", i), e.to_string());
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    let Some(config) = config else {
+        return Ok(linter.diagnostics);
+    };
+
+    if let Err(e) = Url::parse(&config.base_url) {
+        linter.error(
+            "base_url",
+            format!("malformed URL `{}`: {}", config.base_url, e),
+        );
+    }
+
+    for (i, p) in config.redact.iter().enumerate() {
+        if let Err(e) = glob::Pattern::new(p) {
+            linter.error(
+                &format!("redact[{}]", i),
+                format!("invalid pattern `{}`: {}", p, e),
+            );
+        }
+    }
+
+    let subcommands = args::Args::command()
+        .get_subcommands()
+        .flat_map(|c| c.get_all_aliases().chain([c.get_name()]))
+        .chain(["help"])
+        .map(|s| s.to_owned())
+        .collect::<HashSet<_>>();
+    let mut names = HashSet::new();
+    for (i, r) in &requests {
+        if (!names.insert(&r.name)) {
+            linter.error(
+                &format!("requests[{}].name", i),
+                format!("two or more entries have the same name: {}", r.name),
+            );
+        }
+        if (subcommands.contains(&r.name)) {
+            linter.warning(
+                &format!("requests[{}].name", i),
+                format!(
+                    "`ycurl {}` runs the subcommand, so this request can be run only by its index",
+                    r.name
+                ),
+            );
+        }
+    }
+
+    //global variables, which are scanned in order as in `config::create_local_variables()`
+    let mut variables = IndexMap::new();
+    let mut global_used = HashSet::new();
+    for (k, v) in &config.variables {
+        linter.check_references(
+            &format!("variables.{}", k),
+            v,
+            &variables,
+            &HashSet::new(),
+            &mut global_used,
+            &mut HashSet::new(),
+        );
+        variables.insert(k.clone(), linter.expand(v, &variables));
+    }
+
+    for (k, v) in &config.default_headers {
+        let path = format!("default_headers.{}", k);
+        for s in [k, v] {
+            linter.check_references(
+                &path,
+                s,
+                &variables,
+                &HashSet::new(),
+                &mut global_used,
+                &mut HashSet::new(),
+            );
+        }
+        let (k, v) = (linter.expand(k, &variables), linter.expand(v, &variables));
+        linter.check_header(&path, &k, &v);
+        linter.check_unreachable_type_cast(&path, &v);
+    }
+
+    for (i, r) in &requests {
+        linter.lint_request(*i, r, &config, &variables, &mut global_used);
+    }
+
+    //Personal variables in the global config file are not necessarily used in every project, nor are the captured ones.
+    for k in config.variables.keys() {
+        if (!global_used.contains(k)
+            && !raw.global_variables.contains(k)
+            && !raw.captured_variables.contains(k))
+        {
+            linter.warning(
+                &format!("variables.{}", k),
+                format!("variable `{}` is never used", k),
+            );
+        }
+    }
+
+    Ok(linter.diagnostics)
+}
+
+//collects the paths of the values in `v` (used for the files whose positions are unknown)
+fn collect_paths(v: &Value, path: String, out: &mut HashMap<String, Option<(usize, usize)>>) {
+    match (v) {
+        Value::Array(l) => {
+            for (i, e) in l.iter().enumerate() {
+                collect_paths(e, format!("{}[{}]", path, i), out);
+            }
+        }
+        Value::Object(o) => {
+            for (k, e) in o {
+                if (path.is_empty()) {
+                    collect_paths(e, k.clone(), out);
+                } else {
+                    collect_paths(e, format!("{}.{}", path, k), out);
+                }
+            }
+        }
+        _ => (),
+    }
+    out.insert(path, None);
+}
+
+//returns the paths in the file with their positions if available (i.e. if the file is JSON)
+fn locate(file: &Path) -> HashMap<String, Option<(usize, usize)>> {
+    let s = match (config::read_config_file(file)) {
+        Ok(s) => s,
+        Err(_) => return HashMap::new(),
+    };
+    let format = Format::from_path(&file.to_string_lossy());
+    if (format == Format::Json) {
+        return jsonc::locate(&s)
+            .into_iter()
+            .map(|(k, v)| (k, Some(v)))
+            .collect();
+    }
+    let mut ret = HashMap::new();
+    if let Ok(v) = config::from_str::<Value>(&s, format) {
+        collect_paths(&v, String::new(), &mut ret);
+    }
+    ret
+}
+
+//prints the diagnostics like `ycurl.json:12:9: error: requests[0].url: malformed URL ...`
+//Paths of requests are translated into the ones in the files where the requests are defined.
+pub fn print(diagnostics: &[Diagnostic], raw: &RawConfig) {
+    let locations = raw
+        .files
+        .iter()
+        .map(|f| (f.as_path(), locate(f)))
+        .collect::<Vec<_>>();
+    let request_regex = Regex::new(r#"^requests\[(\d+)\]"#).unwrap();
+
+    for d in diagnostics {
+        let (file, path) = if let Some(c) = request_regex.captures(&d.path) {
+            let i = c[1].parse::<usize>().unwrap();
+            let (file, j) = &raw.request_origins[i];
+            (
+                file.as_path(),
+                request_regex
+                    .replace(&d.path, format!("requests[{}]", j))
+                    .into_owned(),
+            )
+        } else {
+            let file = locations
+                .iter()
+                .find(|(_, m)| m.contains_key(&d.path))
+                .map(|(f, _)| *f)
+                .unwrap_or(raw.files[0].as_path());
+            (file, d.path.clone())
+        };
+
+        let position = locations
+            .iter()
+            .find(|(f, _)| *f == file)
+            .and_then(|(_, m)| m.get(&path).cloned().flatten())
+            .map(|(line, column)| format!(":{}:{}", line, column))
+            .unwrap_or_default();
+        let severity = match (d.severity) {
+            Severity::Error => super::paint("031", "error"),
+            Severity::Warning => super::paint("033", "warning"),
+        };
+        if (path.is_empty()) {
+            println!(
+                "{}{}: {}: {}",
+                file.display(),
+                position,
+                severity,
+                d.message
+            );
+        } else {
+            println!(
+                "{}{}: {}: {}: {}",
+                file.display(),
+                position,
+                severity,
+                path,
+                d.message
+            );
+        }
+    }
+
+    let num_errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    println!(
+        "{} error(s), {} warning(s)",
+        num_errors,
+        diagnostics.len() - num_errors
+    );
+}
+
+#[cfg(test)]
+mod tests_lint {
+    //{{{
+    use super::*;
+
+    use serde_json::json;
+
+    fn raw_config(value: Value) -> RawConfig {
+        RawConfig {
+            value,
+            files: vec![],
+            request_origins: vec![],
+            global_variables: vec!["personal".to_owned()],
+            captured_variables: vec![],
+        }
+    }
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let input = json!({
+            "base_url": "http://example.com",
+            "variables": {
+                "id": "123",
+                "name": "${id}_${undefined1}",
+                "personal": "x",
+                "unused": "x"
+            },
+            "default_headers": {
+                "Content-Type": "application/json",
+                "Bad Name": "x"
+            },
+            "requests": [
+                {
+                    "name": "a",
+                    "variables": {
+                        "local": "1",
+                        "unused_local": "${local}",
+                        "out": "out"
+                    },
+                    "url": "/users/${local}",
+                    "method": "POST",
+                    "save_to": "${out}/${undefined3}.json",
+                    "headers": {
+                        "X-Id": "number:${id}",
+                        "X-Bad": "a\nb"
+                    },
+                    "body": "not JSON ${undefined2}"
+                },
+                {
+                    "name": "a",
+                    "url": "http://[::1",
+                    "method": "POST",
+                    "headers": {
+                        "content-type": "text/plain"
+                    },
+                    "body": {
+                        "n": "number:${name}",
+                        "b": "bool:true"
+                    }
+                }
+            ]
+        });
+
+        let actual = lint(&raw_config(input));
+        println!("{:?}", actual);
+        assert!(actual.is_ok());
+        let actual = actual
+            .unwrap()
+            .into_iter()
+            .map(|d| (d.severity, d.path, d.message))
+            .collect::<HashSet<_>>();
+
+        let expected = [
+            (
+                Severity::Error,
+                "requests[1].name",
+                "two or more entries have the same name: a",
+            ),
+            (
+                Severity::Error,
+                "variables.name",
+                "variable `undefined1` is not defined",
+            ),
+            (
+                Severity::Error,
+                "default_headers.Bad Name",
+                "invalid header name: `Bad Name`",
+            ),
+            (
+                Severity::Error,
+                "requests[0].body",
+                "variable `undefined2` is not defined",
+            ),
+            (
+                Severity::Error,
+                "requests[0].save_to",
+                "variable `undefined3` is not defined",
+            ),
+            (
+                Severity::Warning,
+                "requests[0].headers.X-Id",
+                "type cast is performed only inside a map-typed `body`, so `number:` is sent as it is",
+            ),
+            (
+                Severity::Error,
+                "requests[0].headers.X-Bad",
+                "invalid header value: `a\nb`",
+            ),
+            (
+                Severity::Error,
+                "requests[0].body",
+                "`Content-Type` is `application/json` but `body` is not a valid JSON",
+            ),
+            (
+                Severity::Warning,
+                "requests[0].variables.unused_local",
+                "variable `unused_local` is never used",
+            ),
+            (
+                Severity::Error,
+                "requests[1].url",
+                "malformed URL `http://[::1`: invalid IPv6 address",
+            ),
+            (
+                Severity::Error,
+                "requests[1].body.n",
+                "`123_${undefined1}` cannot be cast to a number",
+            ),
+            (
+                Severity::Warning,
+                "requests[1].body",
+                "map-typed `body` is sent as JSON but `Content-Type` is `text/plain`",
+            ),
+            (
+                Severity::Warning,
+                "variables.unused",
+                "variable `unused` is never used",
+            ),
+        ]
+        .into_iter()
+        .map(|(s, p, m)| (s, p.to_owned(), m.to_owned()))
+        .collect::<HashSet<_>>();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        let input = json!({
+            "base_url": "http://example.com",
+            "requests": [
+                {"name": "a", "url": "/", "method": "GET", "unknown": 1}
+            ]
+        });
+        let actual = lint(&raw_config(input)).unwrap();
+        assert_eq!(1, actual.len());
+        assert_eq!(Severity::Error, actual[0].severity);
+        assert!(actual[0].message.contains("unknown field `unknown`"));
+        assert_eq!("requests[0]", actual[0].path);
+
+        //a malformed request does not hide the problems in the others
+        let input = json!({
+            "base_url": "http://example.com",
+            "variables": {"unused": "x"},
+            "requests": [
+                {"name": "a", "url": "/", "method": "GET", "unknown": 1},
+                {"name": "b", "url": "/", "method": "FETCH"},
+                {"name": "c", "url": "http://[::1", "method": "GET"},
+                {"name": "lint", "protocol": "websocket", "url": "/", "method": "GET", "websocket": {"expect": [".ok", "$.["]}}
+            ]
+        });
+        let actual = lint(&raw_config(input))
+            .unwrap()
+            .into_iter()
+            .map(|d| (d.severity, d.path))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (Severity::Error, "requests[0]".to_owned()),
+                (Severity::Error, "requests[1]".to_owned()),
+                (Severity::Warning, "requests[3].name".to_owned()),
+                (Severity::Error, "requests[2].url".to_owned()),
+                (
+                    Severity::Error,
+                    "requests[3].websocket.expect[1]".to_owned()
+                ),
+                (Severity::Warning, "variables.unused".to_owned()),
+            ],
+            actual
+        );
+    }
+
+    #[test]
+    // #[ignore]
+    fn test03() {
+        //the fields of a GraphQL request
+        let input = json!({
+            "base_url": "http://example.com",
+            "requests": [
+                {
+                    "name": "a",
+                    "variables": {"op": "GetUser", "id": "1x"},
+                    "url": "/graphql",
+                    "method": "GET",
+                    "graphql": {
+                        "query": "query GetUser($id: Int) { user(id: $id) { ${field} } }",
+                        "variables": {"id": "number:${id}"},
+                        "operationName": "${op}"
+                    }
+                },
+                {
+                    "name": "b",
+                    "url": "/graphql",
+                    "method": "POST",
+                    "graphql": {"query": "{ a }", "query_file": "a.graphql"}
+                },
+                //and of a WebSocket request
+                {
+                    "name": "c",
+                    "variables": {"room": "1", "token": "x"},
+                    "protocol": "websocket",
+                    "url": "ws://example.com/chat",
+                    "method": "POST",
+                    "websocket": {
+                        "messages": [
+                            {"text": "join ${room}"},
+                            {"json": {"auth": {"token": "${token}"}, "to": "${user}"}}
+                        ]
+                    }
+                },
+                {
+                    "name": "d",
+                    "protocol": "websocket",
+                    "url": "ws://example.com/chat",
+                    "method": "GET",
+                    "websocket": {"timeout": "soon"}
+                }
+            ]
+        });
+        let actual = lint(&raw_config(input))
+            .unwrap()
+            .into_iter()
+            .map(|d| (d.severity, d.path, d.message))
+            .collect::<Vec<_>>();
+        let expected = [
+            (
+                Severity::Error,
+                "requests[0].graphql.query",
+                "variable `field` is not defined",
+            ),
+            (
+                Severity::Error,
+                "requests[0].graphql.variables.id",
+                "`1x` cannot be cast to a number",
+            ),
+            (
+                Severity::Error,
+                "requests[0].method",
+                "a GraphQL request must be `POST`",
+            ),
+            (
+                Severity::Error,
+                "requests[1].graphql",
+                "exactly one of `query` and `query_file` is required",
+            ),
+            (
+                Severity::Error,
+                "requests[2].websocket.messages[1].json.to",
+                "variable `user` is not defined",
+            ),
+            (
+                Severity::Error,
+                "requests[2].method",
+                "a WebSocket request must be `GET`",
+            ),
+            (
+                Severity::Error,
+                "requests[3].websocket.timeout",
+                "invalid duration `soon`: expected number at 0",
+            ),
+        ]
+        .into_iter()
+        .map(|(s, p, m)| (s, p.to_owned(), m.to_owned()))
+        .collect::<Vec<_>>();
+        assert_eq!(expected, actual);
+    }
+    //}}}
+}
@@ -1,39 +1,524 @@
 use std::{
     env,
-    error::Error,
     fs::{self, File},
     io::Write,
-    path::Path,
+    path::{Path, PathBuf},
 };
 
+use base64::prelude::{Engine, BASE64_STANDARD};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::client::Response;
+use super::config;
+use super::error::Error;
+use super::redact::Redactor;
+use super::websocket::{Data, Direction, Frame, Transcript};
+
+//the maximum number of bytes of a response body written to the structured log
+pub const MAX_LOGGED_BODY: usize = 64 * 1024;
+
 pub struct Logger {
+    path: PathBuf,
     log_file: File,
+    //the structured log in JSON Lines
+    jsonl_path: PathBuf,
+    jsonl_file: File,
+    redactor: Redactor,
+}
+
+fn is_false(b: &bool) -> bool {
+    !b
+}
+
+//returns `~/logs`, creating it if not exists
+pub fn log_dir() -> Result<PathBuf, Error> {
+    let p = PathBuf::from(format!("{}/logs", env::var("HOME").unwrap()));
+    if (!p.is_dir()) {
+        fs::create_dir(&p).map_err(|e| Error::Io {
+            path: p.clone(),
+            source: e,
+        })?;
+    }
+    Ok(p)
+}
+
+//returns the path of the structured log
+pub fn jsonl_path() -> Result<PathBuf, Error> {
+    Ok(log_dir()?.join("ycurl.jsonl"))
+}
+
+fn open(p: &Path) -> Result<File, Error> {
+    fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(p)
+        .map_err(|e| Error::Io {
+            path: p.to_owned(),
+            source: e,
+        })
 }
 
 impl Logger {
-    pub fn new() -> Result<Self, Box<dyn Error>> {
-        let log_dir = format!("{}/logs", env::var("HOME").unwrap());
-        let p = Path::new(&log_dir);
-        if (!p.is_dir()) {
-            fs::create_dir(p)?;
+    //Both logs are written through `redactor`.
+    pub fn new(redactor: Redactor) -> Result<Self, Error> {
+        let path = log_dir()?.join("ycurl.txt");
+        let jsonl_path = jsonl_path()?;
+        Ok(Self {
+            log_file: open(&path)?,
+            path,
+            jsonl_file: open(&jsonl_path)?,
+            jsonl_path,
+            redactor,
+        })
+    }
+
+    pub fn redactor(&self) -> &Redactor {
+        &self.redactor
+    }
+
+    pub fn log(&mut self, s: &str) -> Result<(), Error> {
+        self.log_file
+            .write_all((self.redactor.text(s) + "\n").as_bytes())
+            .map_err(|e| Error::Io {
+                path: self.path.clone(),
+                source: e,
+            })
+    }
+
+    //appends `record` to the structured log as a line
+    pub fn record(&mut self, record: &Record) -> Result<(), Error> {
+        let line = serde_json::to_string(&record.redact(&self.redactor))? + "\n";
+        self.jsonl_file
+            .write_all(line.as_bytes())
+            .map_err(|e| Error::Io {
+                path: self.jsonl_path.clone(),
+                source: e,
+            })
+    }
+}
+
+//a line of the structured log, which is written for each HTTP exchange or WebSocket connection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    //when the request was started, in RFC 3339
+    pub timestamp: String,
+    //the absolute path of the config file
+    pub config: String,
+    //the name of the request in the config file
+    pub name: String,
+    pub request: RecordedRequest,
+    //`None` if the request failed
+    pub response: Option<RecordedResponse>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    //in milliseconds, from when the request was sent to when the body was read
+    pub duration: Option<f64>,
+    //the options in effect (from both the config file and the command line), with which `ycurl replay` sends the request again
+    #[serde(default)]
+    pub cli_options: config::CLIOptions,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Map<String, Value>,
+    //in base64 if `body_base64` is set, which is the case when the body is not UTF-8
+    pub body: Option<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub body_base64: bool,
+}
+
+impl RecordedRequest {
+    //`req` is the request built by `client::Client::request()`
+    pub fn new(req: &reqwest::Request) -> Self {
+        let body = req.body().and_then(|b| b.as_bytes());
+        Self {
+            method: req.method().to_string(),
+            url: req.url().to_string(),
+            headers: super::headers_to_map(req.headers()),
+            body: body.map(|b| match (std::str::from_utf8(b)) {
+                Ok(s) => s.to_owned(),
+                Err(_) => BASE64_STANDARD.encode(b),
+            }),
+            body_base64: body.is_some_and(|b| std::str::from_utf8(b).is_err()),
         }
+    }
 
-        let log_file = format!("{}/ycurl.txt", log_dir);
-        let p = Path::new(&log_file);
-        if (!p.is_file()) {
-            File::create(p)?;
+    //returns the body as it was sent
+    pub fn body_bytes(&self) -> Option<Vec<u8>> {
+        let body = self.body.as_ref()?;
+        if (self.body_base64) {
+            BASE64_STANDARD.decode(body).ok()
+        } else {
+            Some(body.clone().into_bytes())
         }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    //the final URL after redirects
+    pub url: String,
+    pub headers: Map<String, Value>,
+    //empty if the body is saved to a file
+    pub body: String,
+    //whether `body` has been cut at `MAX_LOGGED_BODY` bytes
+    pub truncated: bool,
+    //the size of the whole body in bytes
+    pub size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub saved_to: Option<String>,
+}
 
-        let file = fs::OpenOptions::new()
-            .append(true)
-            .create(true)
-            .open(&log_file)?;
-        Ok(Self { log_file: file })
+//cuts `body` at `MAX_LOGGED_BODY` bytes (at a character boundary), returning whether it has been cut
+fn truncate(mut body: String) -> (String, bool) {
+    if (body.len() <= MAX_LOGGED_BODY) {
+        return (body, false);
+    }
+    let mut end = MAX_LOGGED_BODY;
+    while (!body.is_char_boundary(end)) {
+        end -= 1;
     }
+    body.truncate(end);
+    (body, true)
+}
 
-    pub fn log(&mut self, s: &str) -> Result<(), Box<dyn Error>> {
-        self.log_file
-            .write_all((s.to_owned() + "\n").as_bytes())
-            .map_err(|e| e.into())
+//formats a WebSocket message as a line of the logs like `> hello`, where `>` is for a sent one and `<` for a received one
+pub fn frame_line(frame: &Frame, redactor: &Redactor) -> String {
+    let arrow = match (frame.direction) {
+        Direction::Sent => ">",
+        Direction::Received => "<",
+    };
+    match (&frame.data) {
+        Data::Text(s) => format!("{} {}", arrow, redactor.message(s)),
+        Data::Binary(b) => format!("{} (binary data ({} bytes))", arrow, b.len()),
+    }
+}
+
+impl Record {
+    //`req` is the request built by `client::Client::request()`, without which the method and the URL are taken from `request`
+    pub fn new(
+        config_file: &str,
+        cli_options: &config::CLIOptions,
+        request: &config::Request,
+        req: Option<&reqwest::Request>,
+    ) -> Self {
+        let config = fs::canonicalize(config_file)
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or(config_file.to_owned());
+        let name = request.name.clone();
+        let request = match (req) {
+            Some(req) => RecordedRequest::new(req),
+            None => RecordedRequest {
+                method: format!("{:?}", request.method).to_uppercase(),
+                url: request.url.clone(),
+                headers: Map::new(),
+                body: request.body.as_ref().map(|b| match (b) {
+                    Value::String(s) => s.clone(),
+                    v => v.to_string(),
+                }),
+                body_base64: false,
+            },
+        };
+        Self {
+            timestamp: Local::now().to_rfc3339(),
+            config,
+            name,
+            request,
+            response: None,
+            error: None,
+            duration: None,
+            cli_options: cli_options.clone(),
+        }
+    }
+
+    //also sets `timestamp` to when the request was actually sent, which may be later than when the record was created (e.g. with `--jobs`)
+    pub fn with_response(mut self, response: &Response) -> Self {
+        //the beginning of a long streaming response has already been dropped
+        let (body, truncated) = truncate(response.text());
+        let truncated = truncated || (response.dropped != 0);
+        self.timestamp = response.started.to_rfc3339();
+        self.response = Some(RecordedResponse {
+            status: response.status.as_u16(),
+            url: response.url.to_string(),
+            headers: super::headers_to_map(&response.headers),
+            body,
+            truncated,
+            size: response.size(),
+            saved_to: response
+                .saved
+                .as_ref()
+                .map(|s| s.path.display().to_string()),
+        });
+        self.duration = Some(response.timings.total().as_micros() as f64 / 1000.0);
+        self
+    }
+
+    //The response of a WebSocket request is the handshake response, whose body is the messages sent (`>`) and received (`<`), one per line.
+    //Each message is redacted with `redactor` here, as the secrets in a JSON message cannot be found in the joined lines.
+    pub fn with_transcript(mut self, transcript: &Transcript, redactor: &Redactor) -> Self {
+        let lines = transcript
+            .frames
+            .iter()
+            .map(|f| frame_line(f, redactor))
+            .collect::<Vec<_>>();
+        let (body, truncated) = truncate(lines.join("\n"));
+        self.response = Some(RecordedResponse {
+            status: transcript.status.as_u16(),
+            url: transcript.url.to_string(),
+            headers: super::headers_to_map(&transcript.headers),
+            body,
+            truncated,
+            size: transcript.frames.iter().map(|f| f.data.len() as u64).sum(),
+            saved_to: None,
+        });
+        self.duration = Some(transcript.total.as_micros() as f64 / 1000.0);
+        self
+    }
+
+    pub fn with_error(mut self, e: &Error) -> Self {
+        self.error = Some(e.to_string());
+        self
+    }
+
+    //returns a copy with the secrets hidden
+    pub fn redact(&self, redactor: &Redactor) -> Self {
+        let headers = |m: &Map<String, Value>| {
+            let mut v = Value::Object(m.clone());
+            redactor.json(&mut v);
+            match (v) {
+                Value::Object(m) => m,
+                _ => unreachable!(),
+            }
+        };
+        let mut ret = self.clone();
+        ret.request.url = redactor.url(&self.request.url);
+        ret.request.headers = headers(&self.request.headers);
+        if (self.request.body_base64) {
+            //a binary body in which a secret is hidden is written as text, which is rebuilt from the config file on replay anyway
+            if let Some(b) = self.request.body_bytes() {
+                let text = String::from_utf8_lossy(&b);
+                let redacted = redactor.body(&text);
+                if (redacted != text) {
+                    ret.request.body = Some(redacted);
+                    ret.request.body_base64 = false;
+                }
+            }
+        } else {
+            ret.request.body = self.request.body.as_deref().map(|b| redactor.body(b));
+        }
+        if let Some(r) = &mut ret.response {
+            r.url = redactor.url(&r.url);
+            r.headers = headers(&r.headers);
+            r.body = redactor.body(&r.body);
+        }
+        ret.error = self.error.as_deref().map(|e| redactor.text(e));
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests_logger {
+    //{{{
+    use super::*;
+
+    use std::time::Duration;
+
+    use reqwest::{header::HeaderMap, StatusCode, Version};
+    use serde_json::json;
+
+    use super::super::client::Timings;
+    use super::super::websocket::End;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let request: config::Request = serde_json::from_value(json!({
+            "name": "create_user",
+            "url": "/users",
+            "method": "POST",
+            "body": {"name": "Mike"},
+        }))
+        .unwrap();
+        let record = Record::new(
+            "/non/existent/ycurl.json",
+            &Default::default(),
+            &request,
+            None,
+        );
+        assert_eq!("/non/existent/ycurl.json", record.config);
+        assert_eq!("create_user", record.name);
+        assert_eq!("POST", record.request.method);
+        assert_eq!(Some(r#"{"name":"Mike"}"#), record.request.body.as_deref());
+
+        let req = reqwest::Client::new()
+            .post("http://localhost:3000/users")
+            .header("Content-Type", "application/json")
+            .body("{}")
+            .build()
+            .unwrap();
+        let record = Record::new(
+            "/non/existent/ycurl.json",
+            &Default::default(),
+            &request,
+            Some(&req),
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "text/plain".parse().unwrap());
+        let response = Response {
+            started: Local::now(),
+            url: "http://localhost:3000/users/1".parse().unwrap(),
+            redirects: vec![],
+            status: StatusCode::CREATED,
+            version: Version::HTTP_11,
+            headers,
+            body: "あ".repeat(MAX_LOGGED_BODY).into_bytes(),
+            dropped: 0,
+            saved: None,
+            encoded: None,
+            timings: Timings {
+                wait: Duration::from_millis(2),
+                receive: Duration::from_micros(500),
+                ..Default::default()
+            },
+        };
+
+        let v = serde_json::to_value(record.clone().with_response(&response)).unwrap();
+        assert_eq!(
+            json!({
+                "method": "POST",
+                "url": "http://localhost:3000/users",
+                "headers": {"content-type": "application/json"},
+                "body": "{}",
+            }),
+            v["request"]
+        );
+        assert_eq!(201, v["response"]["status"]);
+        assert_eq!("http://localhost:3000/users/1", v["response"]["url"]);
+        assert_eq!(json!(true), v["response"]["truncated"]);
+        assert_eq!(json!(MAX_LOGGED_BODY * 3), v["response"]["size"]);
+        //cut at a character boundary
+        assert_eq!(
+            MAX_LOGGED_BODY / 3 * 3,
+            v["response"]["body"].as_str().unwrap().len()
+        );
+        assert_eq!(json!(2.5), v["duration"]);
+        assert!(v.get("error").is_none());
+
+        let mut secret = record.clone();
+        secret.request.url = "http://localhost:3000/users?token=xyz".to_owned();
+        secret
+            .request
+            .headers
+            .insert("authorization".to_owned(), json!("Bearer xyz"));
+        let v = serde_json::to_value(secret.redact(&Redactor::default())).unwrap();
+        assert_eq!(
+            "http://localhost:3000/users?token=[REDACTED]",
+            v["request"]["url"]
+        );
+        assert_eq!("[REDACTED]", v["request"]["headers"]["authorization"]);
+        assert_eq!("application/json", v["request"]["headers"]["content-type"]);
+        //nothing derived from the secrets is written
+        assert!(!v.to_string().contains("xyz"));
+        assert!(v["request"].get("digest").is_none());
+
+        let e = Error::Other("failed".to_owned());
+        let v = serde_json::to_value(record.with_error(&e)).unwrap();
+        assert_eq!(json!("failed"), v["error"]);
+        assert_eq!(Value::Null, v["response"]);
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        let request: config::Request = serde_json::from_value(json!({
+            "name": "upload",
+            "url": "/upload",
+            "method": "POST",
+        }))
+        .unwrap();
+
+        //a body which is not UTF-8
+        let req = reqwest::Client::new()
+            .post("http://localhost:3000/upload")
+            .body(vec![0x89, b'P', b'N', b'G', 0xff])
+            .build()
+            .unwrap();
+        let record = Record::new(
+            "/non/existent/ycurl.json",
+            &Default::default(),
+            &request,
+            Some(&req),
+        );
+        assert!(record.request.body_base64);
+        assert_eq!(Some("iVBOR/8="), record.request.body.as_deref());
+        assert_eq!(
+            Some(vec![0x89, b'P', b'N', b'G', 0xff]),
+            record.request.body_bytes()
+        );
+        let v = serde_json::to_value(record.redact(&Redactor::default())).unwrap();
+        assert_eq!(json!(true), v["request"]["body_base64"]);
+        //omitted for a UTF-8 body
+        let req = reqwest::Client::new()
+            .post("http://localhost:3000/upload")
+            .body("abc")
+            .build()
+            .unwrap();
+        let v = serde_json::to_value(Record::new(
+            "/non/existent/ycurl.json",
+            &Default::default(),
+            &request,
+            Some(&req),
+        ))
+        .unwrap();
+        assert!(v["request"].get("body_base64").is_none());
+
+        //a WebSocket connection
+        let transcript = Transcript {
+            url: "ws://localhost:3000/ws".parse().unwrap(),
+            status: StatusCode::SWITCHING_PROTOCOLS,
+            headers: HeaderMap::new(),
+            frames: vec![
+                Frame {
+                    direction: Direction::Sent,
+                    data: Data::Text("ping".to_owned()),
+                },
+                Frame {
+                    direction: Direction::Received,
+                    data: Data::Binary(vec![0; 3]),
+                },
+                Frame {
+                    direction: Direction::Sent,
+                    data: Data::Text(r#"{"token": "xyz"}"#.to_owned()),
+                },
+            ],
+            end: End::Closed,
+            expectations: vec![],
+            handshake: Duration::from_millis(1),
+            total: Duration::from_millis(5),
+        };
+        let v = serde_json::to_value(
+            Record::new(
+                "/non/existent/ycurl.json",
+                &Default::default(),
+                &request,
+                None,
+            )
+            .with_transcript(&transcript, &Redactor::default()),
+        )
+        .unwrap();
+        assert_eq!(101, v["response"]["status"]);
+        assert_eq!("ws://localhost:3000/ws", v["response"]["url"]);
+        assert_eq!(
+            "> ping\n< (binary data (3 bytes))\n> {\"token\":\"[REDACTED]\"}",
+            v["response"]["body"]
+        );
+        assert_eq!(23, v["response"]["size"]);
+        assert_eq!(json!(5.0), v["duration"]);
     }
+    //}}}
 }
@@ -1,27 +1,190 @@
-use std::error::Error;
+use std::{
+    env,
+    io::{self, IsTerminal},
+    process,
+};
 
 use chrono::Local;
-use clap::Parser;
+use clap::{error::ErrorKind, CommandFactory, Parser};
+use futures::StreamExt;
 
 use ycurl::args;
-use ycurl::client::Client;
-use ycurl::config;
-use ycurl::logger::Logger;
+use ycurl::bench;
+use ycurl::client::{self, Client};
+use ycurl::config::{self, Protocol};
+use ycurl::error::Error;
+use ycurl::filter::Filter;
+use ycurl::graphql;
+use ycurl::har;
+use ycurl::history;
+use ycurl::lint::{self, Severity};
+use ycurl::logger::{self, Logger, Record};
+use ycurl::redact::Redactor;
+use ycurl::stream;
+use ycurl::websocket;
+use ycurl::Output;
 
-fn main() -> Result<(), Box<dyn Error>> {
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
+        eprintln!("Error: {}", e);
+        process::exit(e.exit_code());
+    }
+}
+
+async fn run() -> Result<(), Error> {
     let args = args::Args::parse();
 
-    let mut config = config::Config::new(&args.file)?;
+    //ref: |https://no-color.org/|
+    let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    ycurl::set_color(!no_color && io::stdout().is_terminal());
+
+    if let Some(har_file) = &args.import_har {
+        return ycurl::show_har_import(har_file);
+    }
+
+    if let Some(args::Command::Schema) = args.command {
+        ycurl::show_schema();
+        return Ok(());
+    }
+
+    if let Some(args::Command::History {
+        name,
+        status,
+        limit,
+    }) = &args.command
+    {
+        let records = history::load(&logger::jsonl_path()?)?;
+        history::print(&records, name.as_deref(), *status, *limit);
+        return Ok(());
+    }
+
+    //A replayed request does not depend on the config file, whose path is taken from the history for logging and for restoring the secrets hidden in the log.
+    let (config_file, mut config) = if let Some(args::Command::Replay { id }) = &args.command {
+        let record = history::find(*id)?;
+        let original = config::Config::new_with_captured_variables(
+            &record.config,
+            config::find_global_config_file().as_deref(),
+            config::load_captured_variables(&record.config)?,
+        )
+        .ok();
+        let config = history::to_config(&record, record.cli_options.clone(), original.as_ref())?;
+        (record.config, config)
+    } else {
+        let config_file = match (&args.file) {
+            Some(f) => f.clone(),
+            None => config::find_config_file(&env::current_dir().map_err(|e| Error::Io {
+                path: ".".into(),
+                source: e,
+            })?)?
+            .to_string_lossy()
+            .into_owned(),
+        };
+        let global_config_file = config::find_global_config_file();
+
+        if let Some(args::Command::Lint) = args.command {
+            let raw = config::load_raw_config(
+                &config_file,
+                global_config_file.as_deref(),
+                config::load_captured_variables(&config_file)?,
+            )?;
+            let diagnostics = lint::lint(&raw)?;
+            lint::print(&diagnostics, &raw);
+            let count = diagnostics
+                .iter()
+                .filter(|d| d.severity == Severity::Error)
+                .count();
+            if (count != 0) {
+                return Err(Error::Lint { count });
+            }
+            return Ok(());
+        }
+
+        let config = config::Config::new_with_captured_variables(
+            &config_file,
+            global_config_file.as_deref(),
+            config::load_captured_variables(&config_file)?,
+        )?;
+        (config_file, config)
+    };
     if (args.show_headers) {
         config.cli_options.show_headers = true;
     }
+    if (args.timing) {
+        config.cli_options.show_timing = true;
+    }
     if (args.disable_redirect) {
         config.cli_options.disable_redirect = true;
     }
+    if let Some(n) = args.max_redirects {
+        config.cli_options.max_redirects = Some(n);
+    }
+    if (args.same_host_redirects) {
+        config.cli_options.same_host_redirects = true;
+    }
+    if let Some(v) = args.http {
+        config.cli_options.http_version = v;
+    }
+    if (args.compressed) {
+        config.cli_options.compressed = true;
+    }
     if (args.verbose) {
         config.cli_options.verbose = true;
     }
 
+    if let Some(args::Command::Bench {
+        index,
+        requests,
+        concurrency,
+        duration,
+    }) = &args.command
+    {
+        let request = config.request(index)?;
+        if (request.disabled && !args.include_disabled) {
+            return Err(Error::DisabledRequest(request.name.clone()));
+        }
+        if (request.protocol == Protocol::WebSocket) {
+            return Err(websocket_error(
+                request,
+                "a WebSocket request cannot be benchmarked",
+            ));
+        }
+        let client = Client::new(&config, request)?.save_to(None);
+        let options = bench::Options {
+            requests: *requests,
+            concurrency: *concurrency as usize,
+            duration: *duration,
+        };
+        bench::run(&client, options).await?.print();
+        return Ok(());
+    }
+
+    if let Some(args::Command::Introspect { index }) = &args.command {
+        let mut request = config.request(index)?.clone();
+        if (request.protocol == Protocol::WebSocket) {
+            return Err(websocket_error(
+                &request,
+                "a WebSocket request cannot be introspected",
+            ));
+        }
+        request.method = config::HTTPMethod::Post;
+        request.body = None;
+        request.graphql = Some(config::GraphQL {
+            query: Some(graphql::INTROSPECTION_QUERY.to_owned()),
+            ..Default::default()
+        });
+        let response = Client::new(&config, &request)?.save_to(None).send().await?;
+        let body = response.json::<serde_json::Value>().map_err(|_| {
+            Error::Other(format!(
+                "introspection failed: the response is not JSON ({})",
+                response.status
+            ))
+        })?;
+        let operations = graphql::operations(&body)?;
+        ycurl::show_operations(&operations);
+        return Ok(());
+    }
+
     if (args.show_config) {
         return ycurl::show_config(&config);
     }
@@ -31,42 +194,287 @@ fn main() -> Result<(), Box<dyn Error>> {
         return Ok(());
     }
 
-    if (args.index.is_none()) {
+    let is_replay = matches!(args.command, Some(args::Command::Replay { .. }));
+    if (args.index.is_empty() && args.group.is_none() && !is_replay) {
         return ycurl::show_requests(&config);
     }
 
-    let request = if let Ok(i) = args.index.as_ref().unwrap().parse::<usize>() {
-        if (i >= config.requests.len()) {
-            return Err("index out of bounds".into());
+    let requests = if (is_replay) {
+        vec![&config.requests[0]]
+    } else if let Some(group) = &args.group {
+        let l = config
+            .group(group)?
+            .into_iter()
+            .filter(|r| !r.disabled || args.include_disabled)
+            .collect::<Vec<_>>();
+        if (l.is_empty()) {
+            return Err(Error::DisabledGroup(group.clone()));
         }
-        &config.requests[i]
+        l
     } else {
-        #[allow(clippy::collapsible_else_if)]
-        if let Some(r) = config
-            .requests
-            .iter()
-            .find(|r| &r.name == args.index.as_ref().unwrap())
-        {
-            r
-        } else {
-            return Err("no entry found for the name".into());
+        let mut l = vec![];
+        for index in &args.index {
+            let request = config.request(index)?;
+            if (request.disabled && !args.include_disabled) {
+                return Err(Error::DisabledRequest(request.name.clone()));
+            }
+            l.push(request);
         }
+        l
     };
+    let is_concurrent = (requests.len() > 1 || args.group.is_some());
+    if let Some(r) = requests.iter().find(|r| r.protocol == Protocol::WebSocket) {
+        if (is_concurrent) {
+            return Err(websocket_error(
+                r,
+                "a WebSocket request cannot be sent concurrently",
+            ));
+        }
+    }
 
-    if (request.disabled && !args.include_disabled) {
-        return Err("disabled request".into());
+    for r in &requests {
+        config.validate_filters(r)?;
     }
 
-    let mut logger = Logger::new()?;
+    //`--filter` overrides `filter` of the requests
+    let filters = requests
+        .iter()
+        .map(|r| {
+            args.filter
+                .as_deref()
+                .or(r.filter.as_deref())
+                .map(Filter::new)
+                .transpose()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut logger = Logger::new(Redactor::new(&config))?;
+
+    if (args.output_file.is_some() && requests.len() > 1) {
+        args::Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "`--output-file` cannot be used with two or more requests",
+            )
+            .exit();
+    }
+
+    if (requests[0].protocol == Protocol::WebSocket) {
+        if (args.output_file.is_some()) {
+            args::Args::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "`--output-file` cannot be used with a WebSocket request",
+                )
+                .exit();
+        }
+        let request = requests[0];
+        let session = websocket::Session::new(&config, request)?;
+        begin(
+            &args,
+            &config,
+            &mut logger,
+            request,
+            session.request().as_ref(),
+        )?;
+        let record = Record::new(
+            &config_file,
+            &config.cli_options,
+            request,
+            session.request().as_ref(),
+        );
+        let connection = session
+            .connect()
+            .await
+            .map_err(|e| record_error(&mut logger, &record, e))?;
+        return ycurl::print_websocket(
+            connection,
+            &mut logger,
+            &record,
+            &config,
+            request,
+            filters[0].as_ref(),
+            args.output,
+        )
+        .await;
+    }
+
+    let clients = requests
+        .iter()
+        .map(|r| {
+            let mut client = Client::new(&config, r)?;
+            if let Some(f) = &args.output_file {
+                client = client.save_to(Some(config.output_file(r, f)?));
+            }
+            Ok(client.progress(!is_concurrent))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    //copies of the requests, which are used for logging and HAR export after the clients are consumed
+    let reqs = clients.iter().map(|c| c.request()).collect::<Vec<_>>();
+
+    if (!is_concurrent) {
+        //A single request is not sent via `send_all()` so that a streaming response is printed as it arrives.
+        let request = requests[0];
+        let filter = filters[0].as_ref();
+        begin(&args, &config, &mut logger, request, reqs[0].as_ref())?;
+        let record = Record::new(&config_file, &config.cli_options, request, reqs[0].as_ref());
+        let head = clients
+            .into_iter()
+            .next()
+            .unwrap()
+            .send_head()
+            .await
+            .map_err(|e| record_error(&mut logger, &record, e))?;
+        match (stream::Format::of(head.response().content_type().as_ref())) {
+            Some(format) if (!head.is_saved()) => {
+                let (response, summary) = ycurl::print_stream(
+                    head,
+                    format,
+                    &mut logger,
+                    &config,
+                    request,
+                    filter,
+                    args.output,
+                )
+                .await
+                .map_err(|e| record_error(&mut logger, &record, e))?;
+                logger.record(&record.with_response(&response))?;
+                if (!summary.captured.is_empty()) {
+                    config::save_captured_variables(&config_file, &summary.captured)?;
+                }
+                export_har(&args, reqs[0].as_ref(), &response)?;
+            }
+            _ => {
+                let response = head
+                    .read()
+                    .await
+                    .map_err(|e| record_error(&mut logger, &record, e))?;
+                logger.record(&record.with_response(&response))?;
+                export_har(&args, reqs[0].as_ref(), &response)?;
+                print(&args, &config, &mut logger, request, &response, filter)?;
+            }
+        }
+        return Ok(());
+    }
+
+    //The records are created before the requests are sent, and their timestamps are corrected with when each request was actually sent.
+    let records = requests
+        .iter()
+        .zip(&reqs)
+        .map(|(r, req)| Record::new(&config_file, &config.cli_options, r, req.as_ref()))
+        .collect::<Vec<_>>();
+    let mut results = client::send_all(clients, args.jobs as usize, args.order);
+    let mut errors = vec![];
+    let mut is_first = true;
+    while let Some((i, result)) = results.next().await {
+        let request = requests[i];
+        if (args.output == Output::Pretty) {
+            if (!is_first) {
+                println!();
+            }
+            println!(
+                "{}",
+                ycurl::paint("1", &format!("==> {} <==", request.name))
+            );
+        }
+        is_first = false;
+
+        begin(&args, &config, &mut logger, request, reqs[i].as_ref())?;
+        let record = &records[i];
+        let result = match (result) {
+            Ok(response) => {
+                logger.record(&record.clone().with_response(&response))?;
+                Ok(response)
+            }
+            Err(e) => Err(record_error(&mut logger, record, e)),
+        };
+        let result = result.and_then(|response| {
+            export_har(&args, reqs[i].as_ref(), &response)?;
+            print(
+                &args,
+                &config,
+                &mut logger,
+                request,
+                &response,
+                filters[i].as_ref(),
+            )
+        });
+        match (result) {
+            Ok(()) => (),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                errors.push((i, e));
+            }
+        }
+    }
+
+    if (!errors.is_empty()) {
+        let failed = errors.len();
+        let first = errors.into_iter().min_by_key(|(i, _)| *i).unwrap().1;
+        return Err(Error::PartialFailure {
+            failed,
+            total: requests.len(),
+            first: Box::new(first),
+        });
+    }
+
+    Ok(())
+}
+
+//logs the start of an exchange
+fn begin(
+    args: &args::Args,
+    config: &config::Config,
+    logger: &mut Logger,
+    request: &config::Request,
+    req: Option<&reqwest::Request>,
+) -> Result<(), Error> {
     logger.log(&format!(
         "\n-------------------- {} --------------------",
         Local::now().format("%Y/%m/%d(%a)%H:%M:%S")
     ))?;
+    if (config.cli_options.verbose && args.output == Output::Pretty) {
+        ycurl::show_url(request, logger.redactor());
+    }
+    ycurl::log_request(logger, req, request)
+}
 
-    let client = Client::new(&config, request, &mut logger)?;
-    let res = client.send()?;
-
-    ycurl::pretty_print(res, &mut logger, &config)?;
+//writes `record` with `e` to the structured log, ignoring a failure in writing so that `e` is reported
+fn record_error(logger: &mut Logger, record: &Record, e: Error) -> Error {
+    let _ = logger.record(&record.clone().with_error(&e));
+    e
+}
 
+fn export_har(
+    args: &args::Args,
+    req: Option<&reqwest::Request>,
+    response: &client::Response,
+) -> Result<(), Error> {
+    if let (Some(har_file), Some(req)) = (&args.export_har, req) {
+        har::export(har_file, har::Entry::new(req, response))?;
+    }
     Ok(())
 }
+
+fn print(
+    args: &args::Args,
+    config: &config::Config,
+    logger: &mut Logger,
+    request: &config::Request,
+    response: &client::Response,
+    filter: Option<&Filter>,
+) -> Result<(), Error> {
+    match (args.output) {
+        Output::Pretty => ycurl::pretty_print(response, logger, config, request, filter),
+        Output::Raw => ycurl::print_raw(response, logger, filter),
+        Output::Json => ycurl::print_json(&request.name, response, logger, filter),
+    }
+}
+
+fn websocket_error(request: &config::Request, message: &str) -> Error {
+    Error::InvalidConfig {
+        message: message.to_owned(),
+        request: Some(request.name.clone()),
+        path: None,
+    }
+}
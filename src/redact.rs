@@ -0,0 +1,260 @@
+use glob::{MatchOptions, Pattern};
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde_json::Value;
+
+use super::config::Config;
+
+//the string which replaces a secret
+pub const REDACTED: &str = "[REDACTED]";
+
+//header names, JSON keys, query parameter names and variable names whose values are always redacted
+pub const DEFAULT_PATTERNS: [&str; 7] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "*password*",
+    "*token*",
+    "*secret*",
+];
+
+//hides secrets in the logs, the verbose output and `--show-config`
+//A value is hidden when its name (e.g. a header name or a JSON key) matches one of the patterns, and the values of the variables marked with `secret` are hidden wherever they appear.
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    patterns: Vec<Pattern>,
+    //the longest first so that a secret containing another is hidden as a whole
+    values: Vec<String>,
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self {
+            patterns: DEFAULT_PATTERNS
+                .iter()
+                .map(|p| Pattern::new(p).unwrap())
+                .collect(),
+            values: vec![],
+        }
+    }
+}
+
+impl Redactor {
+    //The patterns in `redact` are assumed to have been validated by `Config::new()`.
+    pub fn new(config: &Config) -> Self {
+        let mut ret = Self::default();
+        ret.patterns
+            .extend(config.redact.iter().filter_map(|p| Pattern::new(p).ok()));
+        ret.values = config
+            .secrets
+            .iter()
+            .filter(|v| !v.is_empty())
+            .cloned()
+            .collect();
+        ret.values
+            .sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        ret.values.dedup();
+        ret
+    }
+
+    //whether the value of `name` is hidden
+    pub fn is_secret(&self, name: &str) -> bool {
+        let options = MatchOptions {
+            case_sensitive: false,
+            ..Default::default()
+        };
+        self.patterns.iter().any(|p| p.matches_with(name, options))
+    }
+
+    //hides the values of the secret variables in `s`
+    pub fn text(&self, s: &str) -> String {
+        self.values
+            .iter()
+            .fold(s.to_owned(), |s, v| s.replace(v.as_str(), REDACTED))
+    }
+
+    pub fn headers(&self, headers: &HeaderMap) -> HeaderMap {
+        let mut ret = HeaderMap::new();
+        for (k, v) in headers {
+            let v = if (self.is_secret(k.as_str())) {
+                HeaderValue::from_static(REDACTED)
+            } else {
+                let s = String::from_utf8_lossy(v.as_bytes());
+                let redacted = self.text(&s);
+                if (redacted == s) {
+                    v.clone()
+                } else {
+                    HeaderValue::from_str(&redacted).unwrap_or(HeaderValue::from_static(REDACTED))
+                }
+            };
+            ret.append(k, v);
+        }
+        ret
+    }
+
+    //hides the values of the secret keys at any depth, and the values of the secret variables in the strings
+    //The strings under `url` and `base_url` are redacted as URLs.
+    pub fn json(&self, v: &mut Value) {
+        match (v) {
+            Value::Object(o) => {
+                for (k, v) in o.iter_mut() {
+                    if (self.is_secret(k) && !v.is_null()) {
+                        *v = Value::String(REDACTED.to_owned());
+                    } else if let (Value::String(s), "url" | "base_url") = (&mut *v, k.as_str()) {
+                        *s = self.url(s);
+                    } else {
+                        self.json(v);
+                    }
+                }
+            }
+            Value::Array(l) => l.iter_mut().for_each(|v| self.json(v)),
+            Value::String(s) => *s = self.text(s),
+            _ => (),
+        }
+    }
+
+    //hides the values of the secret parameters in `name=value&...`, leaving the other parts as they are
+    fn form(&self, s: &str) -> String {
+        s.split('&')
+            .map(|pair| match (pair.split_once('=')) {
+                Some((k, _)) if (self.is_secret(&decode(k))) => format!("{}={}", k, REDACTED),
+                _ => pair.to_owned(),
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    //hides the values of the secret query parameters
+    pub fn url(&self, url: &str) -> String {
+        let (url, fragment) = match (url.split_once('#')) {
+            Some((u, f)) => (u, Some(f)),
+            None => (url, None),
+        };
+        let mut ret = match (url.split_once('?')) {
+            Some((path, query)) => format!("{}?{}", path, self.form(query)),
+            None => url.to_owned(),
+        };
+        if let Some(f) = fragment {
+            ret = format!("{}#{}", ret, f);
+        }
+        self.text(&ret)
+    }
+
+    //hides the secrets in a request or response body, which is JSON, form values or any other text
+    //A JSON body is pretty-printed if anything is hidden.
+    pub fn body(&self, body: &str) -> String {
+        if let Ok(v @ (Value::Object(_) | Value::Array(_))) = serde_json::from_str::<Value>(body) {
+            let mut redacted = v.clone();
+            self.json(&mut redacted);
+            if (redacted == v) {
+                return body.to_owned();
+            }
+            return serde_json::to_string_pretty(&redacted).unwrap();
+        }
+        let is_form = !body.is_empty()
+            && !body.contains(char::is_whitespace)
+            && body.split('&').all(|pair| pair.contains('='));
+        if (is_form) {
+            self.text(&self.form(body))
+        } else {
+            self.text(body)
+        }
+    }
+
+    //hides the secrets in a WebSocket message, which is JSON or any other text
+    //Unlike `body()`, a JSON message is kept on one line.
+    pub fn message(&self, message: &str) -> String {
+        if let Ok(v @ (Value::Object(_) | Value::Array(_))) = serde_json::from_str::<Value>(message)
+        {
+            let mut redacted = v.clone();
+            self.json(&mut redacted);
+            if (redacted == v) {
+                return message.to_owned();
+            }
+            return redacted.to_string();
+        }
+        self.body(message)
+    }
+}
+
+fn decode(s: &str) -> String {
+    form_urlencoded::parse(format!("{}=", s).as_bytes())
+        .next()
+        .map(|(k, _)| k.into_owned())
+        .unwrap_or(s.to_owned())
+}
+
+#[cfg(test)]
+mod tests_redact {
+    //{{{
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let mut config: Config = serde_json::from_value(json!({
+            "base_url": "http://localhost",
+            "redact": ["x-api-*"],
+            "requests": [],
+        }))
+        .unwrap();
+        config.secrets = vec!["abc".to_owned(), "abcdef".to_owned()];
+        let redactor = Redactor::new(&config);
+
+        assert!(redactor.is_secret("Authorization"));
+        assert!(redactor.is_secret("X-Auth-Token"));
+        assert!(redactor.is_secret("new_password"));
+        assert!(redactor.is_secret("X-API-Key"));
+        assert!(!redactor.is_secret("Content-Type"));
+
+        assert_eq!("id=[REDACTED]-[REDACTED]", redactor.text("id=abcdef-abc"));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", HeaderValue::from_static("Bearer xyz"));
+        headers.insert("x-user", HeaderValue::from_static("abc"));
+        headers.insert("accept", HeaderValue::from_static("*/*"));
+        let headers = redactor.headers(&headers);
+        assert_eq!(REDACTED, headers["authorization"]);
+        assert_eq!(REDACTED, headers["x-user"]);
+        assert_eq!("*/*", headers["accept"]);
+
+        let mut v = json!({
+            "user": {"name": "Mike", "password": "p", "tokens": [1, 2]},
+            "note": "key is abcdef",
+            "refresh_token": null,
+            "requests": [{"url": "/a?access_token=xyz&id=1"}],
+        });
+        redactor.json(&mut v);
+        assert_eq!(
+            json!({
+                "user": {"name": "Mike", "password": REDACTED, "tokens": REDACTED},
+                "note": "key is [REDACTED]",
+                "refresh_token": null,
+                "requests": [{"url": "/a?access_token=[REDACTED]&id=1"}],
+            }),
+            v
+        );
+
+        assert_eq!(
+            "http://localhost/a?id=1&access_token=[REDACTED]&k=[REDACTED]#top",
+            redactor.url("http://localhost/a?id=1&access_token=xyz&k=abc#top")
+        );
+        assert_eq!(
+            "user=mike&pass%77ord=[REDACTED]",
+            redactor.body("user=mike&pass%77ord=p")
+        );
+        assert_eq!(
+            "{\n  \"password\": \"[REDACTED]\"\n}",
+            redactor.body(r#"{"password": "p"}"#)
+        );
+        assert_eq!("plain [REDACTED] text", redactor.body("plain abc text"));
+        assert_eq!(
+            r#"{"token":"[REDACTED]","n":1}"#,
+            redactor.message(r#"{"token": "x", "n": 1}"#)
+        );
+        assert_eq!("hello", redactor.message("hello"));
+    }
+    //}}}
+}
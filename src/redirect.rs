@@ -0,0 +1,75 @@
+use std::sync::{Arc, Mutex};
+
+use itertools::Itertools;
+use reqwest::{redirect::Policy, Method, StatusCode, Url};
+
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+//a redirect followed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    pub status: StatusCode,
+    pub from: Url,
+    //the URL in `Location`
+    pub to: Url,
+    //the method of the request sent to `to`
+    pub method: Method,
+}
+
+//records the redirects followed, being installed to `reqwest::ClientBuilder` as a redirect policy
+#[derive(Debug, Clone, Default)]
+pub struct Chain(Arc<Mutex<Vec<Redirect>>>);
+
+impl Chain {
+    //returns the recorded redirects and resets them
+    pub fn take(&self) -> Vec<Redirect> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+
+    //Redirects to other hosts (or ports) are not followed when `same_host` is set, and the redirect response is returned as it is.
+    //More than `max` redirects are an error, whose message contains the URLs visited so that a redirect loop can be seen.
+    pub fn policy(&self, method: Method, max: usize, same_host: bool) -> Policy {
+        let chain = self.clone();
+        Policy::custom(move |attempt| {
+            let mut chain = chain.0.lock().unwrap();
+            let previous = attempt.previous();
+            //`previous` contains the URL of the original request and those of the redirects followed
+            let (Some(first), Some(from)) = (previous.first(), previous.last()) else {
+                return attempt.follow();
+            };
+            if (same_host
+                && (attempt.url().host_str() != first.host_str()
+                    || attempt.url().port_or_known_default() != first.port_or_known_default()))
+            {
+                return attempt.stop();
+            }
+            if (previous.len() > max) {
+                let urls = previous.iter().chain([attempt.url()]).join(" -> ");
+                return attempt.error(format!("too many redirects (max: {}): {}", max, urls));
+            }
+            let current = match (chain.last()) {
+                Some(r) if (previous.len() > 1) => &r.method,
+                _ => &method,
+            };
+            let method = next_method(attempt.status(), current);
+            chain.push(Redirect {
+                status: attempt.status(),
+                from: from.clone(),
+                to: attempt.url().clone(),
+                method,
+            });
+            attempt.follow()
+        })
+    }
+}
+
+//mirrors how `reqwest` rewrites the method: `303` changes it to `GET` (except for `HEAD`), `301` and `302` change `POST` to `GET`, and `307` and `308` preserve it along with the body
+fn next_method(status: StatusCode, method: &Method) -> Method {
+    match (status) {
+        StatusCode::SEE_OTHER if (method != Method::HEAD) => Method::GET,
+        StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND if (method == Method::POST) => {
+            Method::GET
+        }
+        _ => method.clone(),
+    }
+}
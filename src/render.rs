@@ -0,0 +1,325 @@
+use encoding_rs::{Encoding, UTF_8};
+use itertools::Itertools;
+use mime::Mime;
+use quick_xml::{events::Event, Reader, Writer};
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use serde_json::Value;
+
+use super::to_string_pretty_four_space_indent;
+
+//the number of bytes shown in a hexdump
+const HEXDUMP_LIMIT: usize = 512;
+
+//the kinds of response bodies, each of which is rendered differently
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Json,
+    //newline-delimited JSON
+    Ndjson,
+    Xml,
+    Html,
+    Yaml,
+    Csv,
+    //tab-separated values
+    Tsv,
+    //`application/x-www-form-urlencoded`
+    Form,
+    Text,
+    Binary,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rendered {
+    pub kind: Kind,
+    pub text: String,
+    //the language passed to `bat` for syntax highlighting
+    pub language: Option<&'static str>,
+}
+
+pub fn content_type(headers: &HeaderMap) -> Option<Mime> {
+    headers.get(CONTENT_TYPE)?.to_str().ok()?.parse().ok()
+}
+
+//decodes `body` with the charset in `content_type` (UTF-8 by default)
+//Invalid sequences are replaced with `U+FFFD`, and a BOM, if any, takes precedence over the charset.
+pub fn decode(body: &[u8], content_type: Option<&Mime>) -> String {
+    let encoding = content_type
+        .and_then(|m| m.get_param(mime::CHARSET))
+        .and_then(|c| Encoding::for_label(c.as_str().as_bytes()))
+        .unwrap_or(UTF_8);
+    encoding.decode(body).0.into_owned()
+}
+
+//determines the kind by `content_type`, or by the content when `content_type` is absent or not specific
+pub fn kind(content_type: Option<&Mime>, body: &[u8]) -> Kind {
+    let Some(m) = content_type else {
+        return sniff(body);
+    };
+    let type_ = m.type_().as_str().to_ascii_lowercase();
+    let subtype = m.subtype().as_str().to_ascii_lowercase();
+    let suffix = m.suffix().map(|s| s.as_str().to_ascii_lowercase());
+    match (type_.as_str(), subtype.as_str(), suffix.as_deref()) {
+        (_, "x-ndjson" | "ndjson" | "jsonl" | "x-jsonlines" | "jsonlines", _) => Kind::Ndjson,
+        (_, "json", _) | (_, _, Some("json")) => Kind::Json,
+        ("text", "html", _) => Kind::Html,
+        (_, "xml", _) | (_, _, Some("xml")) => Kind::Xml,
+        (_, "yaml" | "x-yaml", _) | (_, _, Some("yaml")) => Kind::Yaml,
+        ("text", "csv", _) => Kind::Csv,
+        ("text", "tab-separated-values", _) => Kind::Tsv,
+        ("application", "x-www-form-urlencoded", _) => Kind::Form,
+        //Many servers serve JSON as `text/plain`, which is pretty-printed as before.
+        ("text", "plain", _) if (serde_json::from_str::<Value>(&decode(body, Some(m))).is_ok()) => {
+            Kind::Json
+        }
+        ("text", _, _) => Kind::Text,
+        ("image" | "audio" | "video" | "font", _, _) => Kind::Binary,
+        (
+            "application",
+            "octet-stream" | "pdf" | "zip" | "gzip" | "x-tar" | "wasm" | "protobuf" | "x-protobuf"
+            | "msgpack" | "x-msgpack",
+            _,
+        ) => Kind::Binary,
+        _ if (m.get_param(mime::CHARSET).is_some()) => Kind::Text,
+        _ => sniff(body),
+    }
+}
+
+fn sniff(body: &[u8]) -> Kind {
+    if (serde_json::from_slice::<Value>(body).is_ok()) {
+        Kind::Json
+    } else if (body.contains(&0) || std::str::from_utf8(body).is_err()) {
+        Kind::Binary
+    } else if (body.starts_with(b"<")) {
+        Kind::Html
+    } else {
+        Kind::Text
+    }
+}
+
+//renders the body for display, falling back to the decoded text as it is when the body is malformed
+pub fn render(content_type: Option<&Mime>, body: &[u8]) -> Rendered {
+    let kind = kind(content_type, body);
+    let rendered = |text: String, language: Option<&'static str>| Rendered {
+        kind,
+        text,
+        language,
+    };
+    if (kind == Kind::Binary) {
+        return rendered(hexdump(body), None);
+    }
+
+    let text = decode(body, content_type);
+    match (kind) {
+        Kind::Json => match (serde_json::from_str::<Value>(&text)) {
+            Ok(v) => rendered(to_string_pretty_four_space_indent(v), Some("json")),
+            Err(_) => rendered(text, None),
+        },
+        Kind::Ndjson => {
+            let lines = text
+                .lines()
+                .filter(|l| !l.trim().is_empty())
+                .map(|l| match (serde_json::from_str::<Value>(l)) {
+                    Ok(v) => to_string_pretty_four_space_indent(v),
+                    Err(_) => l.to_owned(),
+                })
+                .join("\n");
+            rendered(lines, Some("json"))
+        }
+        Kind::Xml => match (pretty_xml(&text)) {
+            Some(s) => rendered(s, Some("xml")),
+            None => rendered(text, Some("xml")),
+        },
+        Kind::Html => rendered(text, Some("html")),
+        Kind::Yaml => rendered(text, Some("yaml")),
+        Kind::Csv | Kind::Tsv => {
+            let delimiter = if (kind == Kind::Csv) { b',' } else { b'\t' };
+            match (csv_rows(&text, delimiter)) {
+                Some(rows) if (!rows.is_empty()) => rendered(table(&rows), None),
+                _ => rendered(text, None),
+            }
+        }
+        Kind::Form => {
+            let mut rows = vec![vec!["key".to_owned(), "value".to_owned()]];
+            rows.extend(
+                form_urlencoded::parse(text.trim().as_bytes())
+                    .map(|(k, v)| vec![k.into_owned(), v.into_owned()]),
+            );
+            rendered(table(&rows), None)
+        }
+        Kind::Text | Kind::Binary => rendered(text, None),
+    }
+}
+
+fn pretty_xml(s: &str) -> Option<String> {
+    let mut reader = Reader::from_str(s);
+    reader.config_mut().trim_text(true);
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 4);
+    loop {
+        match (reader.read_event().ok()?) {
+            Event::Eof => break,
+            e => writer.write_event(e).ok()?,
+        }
+    }
+    String::from_utf8(writer.into_inner()).ok()
+}
+
+fn csv_rows(s: &str, delimiter: u8) -> Option<Vec<Vec<String>>> {
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(s.as_bytes())
+        .records()
+        .map(|r| r.ok().map(|r| r.iter().map(|c| c.to_owned()).collect()))
+        .collect()
+}
+
+//aligns the columns, treating the first row as the header
+fn table(rows: &[Vec<String>]) -> String {
+    let n = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let widths = (0..n)
+        .map(|i| {
+            rows.iter()
+                .filter_map(|r| r.get(i))
+                .map(|c| c.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+    let line = |r: &Vec<String>| {
+        widths
+            .iter()
+            .enumerate()
+            .map(|(i, w)| format!("{:<w$}", r.get(i).map_or("", |c| c.as_str()), w = w))
+            .join(" | ")
+            .trim_end()
+            .to_owned()
+    };
+    let mut ret = vec![
+        line(&rows[0]),
+        widths.iter().map(|w| "-".repeat(*w)).join("-+-"),
+    ];
+    ret.extend(rows[1..].iter().map(line));
+    ret.join("\n")
+}
+
+//formats the first `HEXDUMP_LIMIT` bytes like `hexdump -C`
+fn hexdump(body: &[u8]) -> String {
+    let mut lines = body[..body.len().min(HEXDUMP_LIMIT)]
+        .chunks(16)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex = (0..16)
+                .map(|j| {
+                    chunk
+                        .get(j)
+                        .map_or("  ".to_owned(), |b| format!("{:02x}", b))
+                })
+                .collect::<Vec<_>>();
+            let ascii = chunk
+                .iter()
+                .map(|&b| {
+                    if (b.is_ascii_graphic() || b == b' ') {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>();
+            format!(
+                "{:08x}  {}  {}  |{}|",
+                i * 16,
+                hex[..8].join(" "),
+                hex[8..].join(" "),
+                ascii
+            )
+        })
+        .collect::<Vec<_>>();
+    if (body.len() > HEXDUMP_LIMIT) {
+        lines.push(format!("... {} more bytes", body.len() - HEXDUMP_LIMIT));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests_render {
+    //{{{
+    use super::*;
+
+    fn render_as(content_type: &str, body: &[u8]) -> Rendered {
+        render(Some(&content_type.parse().unwrap()), body)
+    }
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let r = render_as("application/problem+json", br#"{"a":1}"#);
+        assert_eq!(Kind::Json, r.kind);
+        assert_eq!("{\n    \"a\": 1\n}", r.text);
+
+        let r = render_as("application/x-ndjson", b"{\"a\":1}\n\n{\"b\":2}\n");
+        assert_eq!(Kind::Ndjson, r.kind);
+        assert_eq!("{\n    \"a\": 1\n}\n{\n    \"b\": 2\n}", r.text);
+
+        let r = render_as("text/xml", b"<a><b x=\"1\">text</b><c/></a>");
+        assert_eq!(Kind::Xml, r.kind);
+        assert_eq!("<a>\n    <b x=\"1\">text</b>\n    <c/>\n</a>", r.text);
+
+        let r = render_as("application/yaml", b"a: 1\n");
+        assert_eq!((Kind::Yaml, Some("yaml")), (r.kind, r.language));
+
+        let r = render_as("text/csv", b"id,name\n1,Alice\n10,\"Bob, Jr.\"\n");
+        assert_eq!(Kind::Csv, r.kind);
+        assert_eq!(
+            "id | name\n---+---------\n1  | Alice\n10 | Bob, Jr.",
+            r.text
+        );
+
+        let r = render_as("application/x-www-form-urlencoded", b"a=1&name=Mike+Smith");
+        assert_eq!(Kind::Form, r.kind);
+        assert_eq!(
+            "key  | value\n-----+-----------\na    | 1\nname | Mike Smith",
+            r.text
+        );
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        //charset-aware decoding
+        let r = render_as("text/plain; charset=ISO-8859-1", b"caf\xe9");
+        assert_eq!(Kind::Text, r.kind);
+        assert_eq!("café", r.text);
+        let r = render_as("text/plain; charset=Shift_JIS", b"\x82\xa0");
+        assert_eq!("あ", r.text);
+
+        let body = (0..=255).cycle().take(600).collect::<Vec<u8>>();
+        let r = render_as("image/png", &body);
+        assert_eq!(Kind::Binary, r.kind);
+        let lines = r.text.lines().collect::<Vec<_>>();
+        assert_eq!(33, lines.len());
+        assert_eq!(
+            "00000040  40 41 42 43 44 45 46 47  48 49 4a 4b 4c 4d 4e 4f  |@ABCDEFGHIJKLMNO|",
+            lines[4]
+        );
+        assert_eq!("... 88 more bytes", lines[32]);
+
+        //JSON served as `text/plain`
+        let r = render_as("text/plain; charset=utf-8", br#"{"a":[1]}"#);
+        assert_eq!((Kind::Json, Some("json")), (r.kind, r.language));
+        assert_eq!("{\n    \"a\": [\n        1\n    ]\n}", r.text);
+        assert_eq!(Kind::Text, render_as("text/plain", b"{a").kind);
+        assert_eq!(Kind::Csv, render_as("text/csv", b"[1]").kind);
+
+        //sniffing without `Content-Type`
+        assert_eq!(Kind::Json, render(None, b"[1]").kind);
+        assert_eq!(Kind::Html, render(None, b"<html></html>").kind);
+        assert_eq!(Kind::Text, render(None, b"hello").kind);
+        assert_eq!(Kind::Binary, render(None, b"\x89PNG\x00").kind);
+
+        //falls back to the text as it is
+        let r = render_as("application/json", b"{");
+        assert_eq!(("{".to_owned(), None), (r.text, r.language));
+    }
+    //}}}
+}
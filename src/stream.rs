@@ -0,0 +1,328 @@
+use indexmap::IndexMap;
+use mime::Mime;
+use serde_json::{Map, Value};
+
+use super::client::{Head, Response};
+use super::config;
+use super::error::Error;
+use super::filter::Filter;
+use super::render::{self, Kind};
+
+//the formats of the responses which are printed as they arrive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    //Server-Sent Events (`text/event-stream`)
+    Sse,
+    //newline-delimited JSON
+    Ndjson,
+}
+
+impl Format {
+    //`None` if `content_type` is not of a streaming response
+    pub fn of(content_type: Option<&Mime>) -> Option<Self> {
+        let m = content_type?;
+        if (m.type_() == mime::TEXT && m.subtype() == mime::EVENT_STREAM) {
+            Some(Format::Sse)
+        } else if (render::kind(Some(m), &[]) == Kind::Ndjson) {
+            Some(Format::Ndjson)
+        } else {
+            None
+        }
+    }
+}
+
+//splits a body, which arrives in arbitrary chunks, into events
+//An SSE event is converted to an object with `event`, `id` (if any) and `data`, and an NDJSON line to the value itself.
+//`data` and a line are parsed as JSON if possible, and kept as a string otherwise.
+//ref: |https://html.spec.whatwg.org/multipage/server-sent-events.html#event-stream-interpretation|
+#[derive(Debug)]
+pub struct Parser {
+    format: Format,
+    //an incomplete line
+    buf: Vec<u8>,
+    //the fields of the SSE event being read
+    event: Option<String>,
+    id: Option<String>,
+    data: Vec<String>,
+}
+
+fn parse_data(s: &str) -> Value {
+    serde_json::from_str(s).unwrap_or_else(|_| Value::String(s.to_owned()))
+}
+
+impl Parser {
+    pub fn new(format: Format) -> Self {
+        Self {
+            format,
+            buf: vec![],
+            event: None,
+            id: None,
+            data: vec![],
+        }
+    }
+
+    //returns the events completed by `chunk`
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<Value> {
+        self.buf.extend_from_slice(chunk);
+        let mut ret = vec![];
+        while let Some(i) = self.buf.iter().position(|&b| b == b'\n') {
+            let line = self.buf.drain(..=i).collect::<Vec<_>>();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(v) = self.line(line.trim_end_matches(['\n', '\r'])) {
+                ret.push(v);
+            }
+        }
+        ret
+    }
+
+    //returns the last event at the end of the body
+    //The last line of NDJSON may lack a newline, while an incomplete SSE event is discarded as the spec says.
+    pub fn finish(&mut self) -> Option<Value> {
+        let line = String::from_utf8_lossy(&std::mem::take(&mut self.buf)).into_owned();
+        match (self.format) {
+            Format::Ndjson => self.line(line.trim_end_matches('\r')),
+            Format::Sse => None,
+        }
+    }
+
+    fn line(&mut self, line: &str) -> Option<Value> {
+        if (self.format == Format::Ndjson) {
+            return if (line.trim().is_empty()) {
+                None
+            } else {
+                Some(parse_data(line))
+            };
+        }
+
+        if (line.is_empty()) {
+            return self.dispatch();
+        }
+        if (line.starts_with(':')) {
+            return None;
+        }
+        let (field, value) = match (line.split_once(':')) {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match (field) {
+            "event" => self.event = Some(value.to_owned()),
+            "id" => self.id = Some(value.to_owned()),
+            "data" => self.data.push(value.to_owned()),
+            //`retry` and unknown fields
+            _ => (),
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<Value> {
+        let event = self.event.take();
+        let id = self.id.take();
+        if (self.data.is_empty()) {
+            return None;
+        }
+        let data = std::mem::take(&mut self.data).join("\n");
+        let mut m = Map::new();
+        m.insert(
+            "event".to_owned(),
+            Value::String(event.unwrap_or("message".to_owned())),
+        );
+        if let Some(id) = id {
+            m.insert("id".to_owned(), Value::String(id));
+        }
+        m.insert("data".to_owned(), parse_data(&data));
+        Some(Value::Object(m))
+    }
+}
+
+//`config::Stream` with the expressions compiled
+#[derive(Debug, Default)]
+pub struct Options {
+    pub capture: IndexMap<String, Filter>,
+    pub until: Option<Filter>,
+    pub max_events: Option<u64>,
+}
+
+impl Options {
+    pub fn new(stream: Option<&config::Stream>) -> Result<Self, Error> {
+        let Some(stream) = stream else {
+            return Ok(Self::default());
+        };
+        Ok(Self {
+            capture: stream
+                .capture
+                .iter()
+                .map(|(k, expr)| Ok((k.clone(), Filter::new(expr)?)))
+                .collect::<Result<_, Error>>()?,
+            until: stream.until.as_deref().map(Filter::new).transpose()?,
+            max_events: stream.max_events,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Summary {
+    pub events: u64,
+    pub captured: IndexMap<String, Value>,
+    //whether the stream was closed by `until` or `max_events` before it ended
+    pub stopped: bool,
+}
+
+//returns whether to continue reading
+//An expression which fails on an event (e.g. `.data.token` against a string `data`) is regarded as having no output, since events of different shapes are usually mixed in a stream.
+fn handle(
+    v: Value,
+    options: &Options,
+    summary: &mut Summary,
+    on_event: &mut impl FnMut(&Value) -> Result<(), Error>,
+) -> Result<bool, Error> {
+    summary.events += 1;
+    on_event(&v)?;
+    for (k, filter) in &options.capture {
+        if let Some(captured) = filter
+            .apply(v.clone())
+            .unwrap_or_default()
+            .into_iter()
+            .rfind(|c| !c.is_null())
+        {
+            summary.captured.insert(k.clone(), captured);
+        }
+    }
+    if let Some(until) = &options.until {
+        if (until
+            .apply(v)
+            .unwrap_or_default()
+            .iter()
+            .any(|o| !matches!(o, Value::Null | Value::Bool(false))))
+        {
+            return Ok(false);
+        }
+    }
+    Ok(options.max_events.is_none_or(|n| summary.events < n))
+}
+
+//reads the body of `head`, calling `on_event` for each event as soon as it arrives
+pub async fn read(
+    head: Head,
+    format: Format,
+    options: &Options,
+    mut on_event: impl FnMut(&Value) -> Result<(), Error>,
+) -> Result<(Response, Summary), Error> {
+    let mut parser = Parser::new(format);
+    let mut summary = Summary::default();
+    let response = head
+        .read_chunks(|chunk| {
+            for v in parser.push(chunk) {
+                if (!handle(v, options, &mut summary, &mut on_event)?) {
+                    summary.stopped = true;
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        })
+        .await?;
+    if (!summary.stopped) {
+        if let Some(v) = parser.finish() {
+            summary.stopped = !handle(v, options, &mut summary, &mut on_event)?;
+        }
+    }
+    Ok((response, summary))
+}
+
+#[cfg(test)]
+mod tests_stream {
+    //{{{
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let mut parser = Parser::new(Format::Sse);
+        assert_eq!(
+            Vec::<Value>::new(),
+            parser.push(b": comment\nevent: update\nid: 1\ndata: {\"a\":")
+        );
+        assert_eq!(
+            vec![json!({"event": "update", "id": "1", "data": {"a": 1}})],
+            parser.push(b" 1}\r\n\r\ndata: line1\ndata: line2\n\nretry: 10\n\n")[..1]
+        );
+        let mut parser = Parser::new(Format::Sse);
+        assert_eq!(
+            vec![
+                json!({"event": "message", "data": "line1\nline2"}),
+                json!({"event": "message", "data": ""}),
+            ],
+            parser.push(b"data: line1\ndata: line2\n\nretry: 10\n\ndata\n\ndata: incomplete")
+        );
+        assert_eq!(None, parser.finish());
+
+        let mut parser = Parser::new(Format::Ndjson);
+        assert_eq!(vec![json!({"a": 1})], parser.push(b"{\"a\": 1}\n\n{\"a\""));
+        assert_eq!(Vec::<Value>::new(), parser.push(b": 2}"));
+        assert_eq!(Some(json!({"a": 2})), parser.finish());
+
+        assert_eq!(
+            Some(Format::Sse),
+            Format::of(Some(&"text/event-stream; charset=utf-8".parse().unwrap()))
+        );
+        assert_eq!(
+            Some(Format::Ndjson),
+            Format::of(Some(&"application/x-ndjson".parse().unwrap()))
+        );
+        assert_eq!(None, Format::of(Some(&mime::APPLICATION_JSON)));
+        assert_eq!(None, Format::of(None));
+    }
+
+    #[test]
+    // #[ignore]
+    fn test02() {
+        let stream = config::Stream {
+            capture: [
+                ("token".to_owned(), ".data.token".to_owned()),
+                ("last".to_owned(), ".id".to_owned()),
+            ]
+            .into_iter()
+            .collect(),
+            until: Some(r#".event == "done""#.to_owned()),
+            max_events: None,
+        };
+        let options = Options::new(Some(&stream)).unwrap();
+        let mut summary = Summary::default();
+        let mut seen = vec![];
+        let mut on_event = |v: &Value| {
+            seen.push(v["id"].clone());
+            Ok(())
+        };
+        let events = [
+            json!({"event": "message", "id": "1", "data": {"token": "a"}}),
+            json!({"event": "message", "id": "2", "data": {}}),
+            json!({"event": "done", "id": "3", "data": "bye"}),
+        ];
+        let results = events
+            .into_iter()
+            .map(|v| handle(v, &options, &mut summary, &mut on_event).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![true, true, false], results);
+        assert_eq!(vec![json!("1"), json!("2"), json!("3")], seen);
+        assert_eq!(3, summary.events);
+        assert_eq!(
+            vec![("token", json!("a")), ("last", json!("3"))],
+            summary
+                .captured
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.clone()))
+                .collect::<Vec<_>>()
+        );
+
+        let options = Options {
+            max_events: Some(2),
+            ..Default::default()
+        };
+        let mut summary = Summary::default();
+        let mut on_event = |_: &Value| Ok(());
+        assert!(handle(json!(1), &options, &mut summary, &mut on_event).unwrap());
+        assert!(!handle(json!(2), &options, &mut summary, &mut on_event).unwrap());
+    }
+    //}}}
+}
@@ -0,0 +1,101 @@
+use std::{
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use tower_layer::Layer;
+use tower_service::Service;
+
+//durations of DNS resolution and connection establishment
+//They are `None` when the step did not happen (e.g. an IP address is given or a connection is reused), and are summed over the connections made while following redirects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Recorded {
+    pub dns: Option<Duration>,
+    //TCP connection and TLS handshake after DNS resolution
+    //They cannot be timed separately: `reqwest` does not expose the boundary between them, and a connector layer cannot replace its TLS connector as the connection type is private.
+    pub connect: Option<Duration>,
+}
+
+fn add(d: &mut Option<Duration>, elapsed: Duration) {
+    *d = Some(d.unwrap_or_default() + elapsed);
+}
+
+//records the durations, being installed to `reqwest::ClientBuilder` both as a DNS resolver and as a connector layer
+#[derive(Debug, Clone, Default)]
+pub struct Recorder(Arc<Mutex<Recorded>>);
+
+impl Recorder {
+    //returns the recorded durations and resets them
+    pub fn take(&self) -> Recorded {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+impl Resolve for Recorder {
+    fn resolve(&self, name: Name) -> Resolving {
+        let recorder = self.clone();
+        Box::pin(async move {
+            let start = Instant::now();
+            let addrs = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .collect::<Vec<SocketAddr>>();
+            add(&mut recorder.0.lock().unwrap().dns, start.elapsed());
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+impl<S> Layer<S> for Recorder {
+    type Service = TimedConnector<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TimedConnector {
+            inner,
+            recorder: self.clone(),
+        }
+    }
+}
+
+//wraps the connector of `reqwest`, which performs DNS resolution, TCP connection and TLS handshake
+#[derive(Debug, Clone)]
+pub struct TimedConnector<S> {
+    inner: S,
+    recorder: Recorder,
+}
+
+impl<S, R> Service<R> for TimedConnector<S>
+where
+    S: Service<R>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let recorder = self.recorder.clone();
+        let start = Instant::now();
+        //the DNS resolution performed in this connection is excluded
+        let dns = recorder.0.lock().unwrap().dns.unwrap_or_default();
+        let future = self.inner.call(request);
+        Box::pin(async move {
+            let ret = future.await;
+            let mut recorded = recorder.0.lock().unwrap();
+            let resolved = recorded.dns.unwrap_or_default().saturating_sub(dns);
+            add(
+                &mut recorded.connect,
+                start.elapsed().saturating_sub(resolved),
+            );
+            ret
+        })
+    }
+}
@@ -0,0 +1,465 @@
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+
+use futures::{SinkExt, StreamExt};
+use reqwest::{header::HeaderMap, StatusCode, Url};
+use serde_json::Value;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{
+    tungstenite::{self, client::IntoClientRequest, error::ProtocolError, Message},
+    MaybeTlsStream, WebSocketStream,
+};
+
+use super::client::{self, Client, UNIX_SOCKET_SCHEME};
+use super::config::{self, Config, Request};
+use super::error::Error;
+use super::filter::Filter;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+//a WebSocket connection opened by a request with `"protocol": "websocket"`
+pub struct Session {
+    name: String,
+    req: reqwest::Request,
+    messages: Vec<config::Message>,
+    expect: Vec<Filter>,
+    timeout: Duration,
+    max_messages: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Data {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl Data {
+    pub fn len(&self) -> usize {
+        match (self) {
+            Data::Text(s) => s.len(),
+            Data::Binary(b) => b.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    //a text message parsed as JSON if possible and kept as a string otherwise (`None` for a binary message)
+    pub fn value(&self) -> Option<Value> {
+        match (self) {
+            Data::Text(s) => {
+                Some(serde_json::from_str(s).unwrap_or_else(|_| Value::String(s.clone())))
+            }
+            Data::Binary(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub direction: Direction,
+    pub data: Data,
+}
+
+//why the connection was closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum End {
+    //closed by the server
+    Closed,
+    Timeout,
+    MaxMessages,
+}
+
+impl End {
+    pub fn as_str(&self) -> &'static str {
+        match (self) {
+            End::Closed => "closed",
+            End::Timeout => "timeout",
+            End::MaxMessages => "max_messages",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    pub url: Url,
+    //the status and the headers of the handshake response
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub frames: Vec<Frame>,
+    pub end: End,
+    //the expressions in `expect`, each with whether it is satisfied
+    pub expectations: Vec<(String, bool)>,
+    pub handshake: Duration,
+    pub total: Duration,
+}
+
+impl Transcript {
+    pub fn received(&self) -> usize {
+        self.frames
+            .iter()
+            .filter(|f| f.direction == Direction::Received)
+            .count()
+    }
+
+    pub fn unmet(&self) -> Vec<String> {
+        self.expectations
+            .iter()
+            .filter(|(_, satisfied)| !satisfied)
+            .map(|(expr, _)| expr.clone())
+            .collect()
+    }
+}
+
+fn is_truthy(filter: &Filter, v: &Value) -> bool {
+    //As with `stream.until`, an expression failing on a message is regarded as having no output.
+    filter
+        .apply(v.clone())
+        .unwrap_or_default()
+        .iter()
+        .any(|o| !matches!(o, Value::Null | Value::Bool(false)))
+}
+
+impl Session {
+    //The URL is built in the same way as an HTTP request, and the scheme `http(s)` is replaced with `ws(s)`.
+    pub fn new(config: &Config, request: &Request) -> Result<Self, Error> {
+        let error = |message: String| Error::InvalidConfig {
+            message,
+            request: Some(request.name.clone()),
+            path: None,
+        };
+        if (config.base_url.starts_with(UNIX_SOCKET_SCHEME)
+            && !client::is_absolute_url(&request.url))
+        {
+            return Err(error(
+                "a WebSocket request cannot be sent over a Unix domain socket".to_owned(),
+            ));
+        }
+        let mut req = Client::new(config, request)?
+            .request()
+            .ok_or_else(|| error(format!("malformed URL: `{}`", request.url)))?;
+        let scheme = match (req.url().scheme()) {
+            "http" | "ws" => "ws",
+            "https" | "wss" => "wss",
+            s => return Err(error(format!("unsupported scheme for WebSocket: `{}`", s))),
+        };
+        req.url_mut().set_scheme(scheme).unwrap();
+
+        let websocket = request.websocket.clone().unwrap_or_default();
+        Ok(Self {
+            name: request.name.clone(),
+            req,
+            messages: websocket.messages,
+            expect: websocket
+                .expect
+                .iter()
+                .map(|expr| Filter::new(expr))
+                .collect::<Result<_, _>>()?,
+            timeout: websocket
+                .timeout
+                .as_deref()
+                .map(humantime::parse_duration)
+                .transpose()
+                .map_err(|e| error(e.to_string()))?
+                .unwrap_or(DEFAULT_TIMEOUT),
+            max_messages: websocket.max_messages,
+        })
+    }
+
+    //returns a copy of the handshake request (used for logging)
+    pub fn request(&self) -> Option<reqwest::Request> {
+        self.req.try_clone()
+    }
+
+    fn error(&self, source: tungstenite::Error) -> Error {
+        Error::WebSocket {
+            request: self.name.clone(),
+            source: Box::new(source),
+        }
+    }
+
+    fn load(&self, message: &config::Message) -> Result<Data, Error> {
+        Ok(match (message) {
+            config::Message::Text(s) => Data::Text(s.clone()),
+            config::Message::Json(v) => Data::Text(serde_json::to_string(v)?),
+            config::Message::File(path) => Data::Binary(fs::read(path).map_err(|e| Error::Io {
+                path: path.into(),
+                source: e,
+            })?),
+        })
+    }
+
+    //opens the connection, reading the files of the messages beforehand so that a missing file does not leave the connection half-used
+    pub async fn connect(self) -> Result<Connection, Error> {
+        let messages = self
+            .messages
+            .iter()
+            .map(|m| self.load(m))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut handshake = self
+            .req
+            .url()
+            .as_str()
+            .into_client_request()
+            .map_err(|e| self.error(e))?;
+        handshake.headers_mut().extend(self.req.headers().clone());
+
+        let start = Instant::now();
+        let (ws, res) = tokio_tungstenite::connect_async(handshake)
+            .await
+            .map_err(|e| self.error(e))?;
+        Ok(Connection {
+            handshake: start.elapsed(),
+            status: res.status(),
+            headers: res.headers().clone(),
+            start,
+            ws,
+            messages,
+            session: self,
+        })
+    }
+}
+
+//an opened connection, whose messages are not sent yet
+pub struct Connection {
+    session: Session,
+    ws: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    messages: Vec<Data>,
+    //the status and the headers of the handshake response
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    start: Instant,
+    handshake: Duration,
+}
+
+impl Connection {
+    //sends the messages and reads the incoming messages until the connection is closed by the server, `timeout` elapses or `max_messages` messages are received
+    //`on_frame` is called for each message as soon as it is sent or received.
+    pub async fn run(
+        self,
+        mut on_frame: impl FnMut(&Frame) -> Result<(), Error>,
+    ) -> Result<Transcript, Error> {
+        let Connection {
+            session,
+            mut ws,
+            messages,
+            status,
+            headers,
+            start,
+            handshake,
+        } = self;
+        let deadline = tokio::time::Instant::from_std(start + session.timeout);
+
+        let mut frames = vec![];
+        for data in messages {
+            let message = match (&data) {
+                Data::Text(s) => Message::text(s.clone()),
+                Data::Binary(b) => Message::binary(b.clone()),
+            };
+            ws.send(message).await.map_err(|e| session.error(e))?;
+            let frame = Frame {
+                direction: Direction::Sent,
+                data,
+            };
+            on_frame(&frame)?;
+            frames.push(frame);
+        }
+
+        let mut received = 0;
+        let mut next = 0;
+        let end = loop {
+            if (session.max_messages.is_some_and(|n| received >= n)) {
+                break End::MaxMessages;
+            }
+            let data = match (tokio::time::timeout_at(deadline, ws.next()).await) {
+                Err(_) => break End::Timeout,
+                Ok(None) => break End::Closed,
+                Ok(Some(Err(
+                    tungstenite::Error::ConnectionClosed
+                    | tungstenite::Error::AlreadyClosed
+                    | tungstenite::Error::Protocol(ProtocolError::ResetWithoutClosingHandshake),
+                ))) => break End::Closed,
+                Ok(Some(Err(e))) => return Err(session.error(e)),
+                Ok(Some(Ok(Message::Text(s)))) => Data::Text(s.to_string()),
+                Ok(Some(Ok(Message::Binary(b)))) => Data::Binary(b.to_vec()),
+                //pings are answered automatically
+                Ok(Some(Ok(_))) => continue,
+            };
+            received += 1;
+            if let (Some(filter), Some(v)) = (session.expect.get(next), data.value()) {
+                if (is_truthy(filter, &v)) {
+                    next += 1;
+                }
+            }
+            let frame = Frame {
+                direction: Direction::Received,
+                data,
+            };
+            on_frame(&frame)?;
+            frames.push(frame);
+        };
+        if (end != End::Closed) {
+            //The server may have gone away, in which case there is nothing to do.
+            let _ = ws.close(None).await;
+        }
+
+        Ok(Transcript {
+            url: session.req.url().clone(),
+            status,
+            headers,
+            frames,
+            end,
+            expectations: session
+                .expect
+                .iter()
+                .enumerate()
+                .map(|(i, f)| (f.expr().to_owned(), i < next))
+                .collect(),
+            handshake,
+            total: start.elapsed(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests_websocket {
+    //{{{
+    use super::*;
+
+    use std::{net::TcpListener, thread};
+
+    //echoes the text messages back with `"echo": true` until the client closes the connection
+    //The signature of the handshake callback is defined by `tungstenite`.
+    #[allow(clippy::result_large_err)]
+    fn serve() -> (String, thread::JoinHandle<Option<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut authorization = None;
+            let mut ws = tungstenite::accept_hdr(
+                stream,
+                |req: &tungstenite::handshake::server::Request, res| {
+                    authorization = req
+                        .headers()
+                        .get("authorization")
+                        .map(|v| v.to_str().unwrap().to_owned());
+                    Ok(res)
+                },
+            )
+            .unwrap();
+            while let Ok(message) = ws.read() {
+                match (message) {
+                    Message::Text(s) => {
+                        let mut v = serde_json::from_str::<Value>(&s).unwrap();
+                        v["echo"] = Value::Bool(true);
+                        ws.send(Message::text(v.to_string())).unwrap();
+                    }
+                    Message::Binary(b) => ws.send(Message::Binary(b)).unwrap(),
+                    Message::Close(_) => break,
+                    _ => (),
+                }
+            }
+            authorization
+        });
+        (base_url, handle)
+    }
+
+    #[test]
+    // #[ignore]
+    fn test01() {
+        let (base_url, handle) = serve();
+        let dir =
+            std::env::temp_dir().join(format!("ycurl_tests_websocket_01_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("data.bin"), b"\x00\x01").unwrap();
+        fs::write(
+            dir.join("ycurl.json"),
+            format!(
+                r#"{{
+                    "base_url": "{}",
+                    "variables": {{"id": "42"}},
+                    "requests": [
+                        {{
+                            "name": "chat",
+                            "protocol": "websocket",
+                            "url": "/chat",
+                            "method": "GET",
+                            "headers": {{"Authorization": "Bearer x"}},
+                            "websocket": {{
+                                "messages": [
+                                    {{"json": {{"id": "${{id}}"}}}},
+                                    {{"file": "{}"}},
+                                    {{"text": "{{\"id\": 2}}"}}
+                                ],
+                                "expect": [".echo", ".id == 2", ".id == 3"],
+                                "timeout": "5s",
+                                "max_messages": 3
+                            }}
+                        }}
+                    ]
+                }}"#,
+                base_url,
+                dir.join("data.bin").display()
+            ),
+        )
+        .unwrap();
+
+        let config = Config::new(&dir.join("ycurl.json").to_string_lossy(), None).unwrap();
+        let session = Session::new(&config, config.request("chat").unwrap()).unwrap();
+        assert!(session
+            .request()
+            .unwrap()
+            .url()
+            .as_str()
+            .starts_with("ws://127.0.0.1:"));
+        let mut seen = vec![];
+        let transcript = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(async {
+                let connection = session.connect().await?;
+                assert_eq!(StatusCode::SWITCHING_PROTOCOLS, connection.status);
+                connection
+                    .run(|frame| {
+                        seen.push(frame.direction);
+                        Ok(())
+                    })
+                    .await
+            })
+            .unwrap();
+        fs::remove_dir_all(dir).unwrap();
+
+        assert_eq!(Some("Bearer x".to_owned()), handle.join().unwrap());
+        assert_eq!(StatusCode::SWITCHING_PROTOCOLS, transcript.status);
+        assert_eq!(End::MaxMessages, transcript.end);
+        assert_eq!(3, transcript.received());
+        assert_eq!(
+            vec![
+                Direction::Sent,
+                Direction::Sent,
+                Direction::Sent,
+                Direction::Received,
+                Direction::Received,
+                Direction::Received,
+            ],
+            seen
+        );
+        assert_eq!(
+            Data::Text(r#"{"id":"42","echo":true}"#.to_owned()),
+            transcript.frames[3].data
+        );
+        assert_eq!(Data::Binary(vec![0, 1]), transcript.frames[4].data);
+        assert_eq!(vec![".id == 3".to_owned()], transcript.unmet());
+    }
+    //}}}
+}